@@ -1,7 +1,6 @@
 #![feature(allocator_api)]
 
 use slab::SlabAllocator;
-use std::alloc::Allocator;
 
 fn main() {
     // Create buffer to allocate into
@@ -16,7 +15,7 @@ fn main() {
     .unwrap();
 
     // Create vector
-    let mut new: Vec<u8, _> = Vec::with_capacity_in(10, allocator.by_ref());
+    let mut new = allocator.vec_with_capacity::<u8>(10);
     // -- do work with vec --
     for i in 0..10 {
         new.push(i);
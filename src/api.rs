@@ -0,0 +1,143 @@
+#[cfg(feature = "nightly")]
+pub use core::alloc::{AllocError, Allocator};
+
+#[cfg(not(feature = "nightly"))]
+pub use self::stable::{AllocError, Allocator};
+
+#[cfg(not(feature = "nightly"))]
+mod stable {
+    use core::alloc::Layout;
+    use core::{fmt, ptr};
+
+    /// The error returned when an allocation cannot be satisfied
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AllocError;
+
+    impl fmt::Display for AllocError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "memory allocation failed")
+        }
+    }
+
+    impl core::error::Error for AllocError {}
+
+    /// Stable copy of `core::alloc::Allocator`, with the same methods and contracts
+    ///
+    /// # Safety
+    /// Memory blocks returned must stay valid until deallocated and must not overlap
+    pub unsafe trait Allocator {
+        /// Allocates a block of memory fitting `layout`
+        fn allocate(&self, layout: Layout) -> Result<ptr::NonNull<[u8]>, AllocError>;
+
+        /// Like [`allocate`](Allocator::allocate) but the memory is zeroed
+        fn allocate_zeroed(&self, layout: Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            let slab = self.allocate(layout)?;
+            // SAFETY: the block was just allocated
+            unsafe { slab.cast::<u8>().write_bytes(0, slab.len()) };
+            Ok(slab)
+        }
+
+        /// Frees the memory at `ptr`
+        ///
+        /// # Safety
+        /// `ptr` must be a live block allocated by this allocator with `layout`
+        unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: Layout);
+
+        /// Extends the block at `ptr`, moving it if needed
+        ///
+        /// # Safety
+        /// `ptr` must be a live block allocated with `old_layout`, and `new_layout` no smaller
+        unsafe fn grow(
+            &self,
+            ptr: ptr::NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            let slab = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), slab.cast().as_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(slab)
+        }
+
+        /// Like [`grow`](Allocator::grow) but the new memory is zeroed
+        ///
+        /// # Safety
+        /// As for [`grow`](Allocator::grow)
+        unsafe fn grow_zeroed(
+            &self,
+            ptr: ptr::NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            let slab = self.allocate_zeroed(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), slab.cast().as_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(slab)
+        }
+
+        /// Shrinks the block at `ptr`, moving it if needed
+        ///
+        /// # Safety
+        /// `ptr` must be a live block allocated with `old_layout`, and `new_layout` no larger
+        unsafe fn shrink(
+            &self,
+            ptr: ptr::NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            let slab = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), slab.cast().as_ptr(), new_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(slab)
+        }
+
+        /// A reference to the allocator, itself an allocator
+        fn by_ref(&self) -> &Self
+        where
+            Self: Sized,
+        {
+            self
+        }
+    }
+
+    unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+        fn allocate(&self, layout: Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            (**self).allocate(layout)
+        }
+
+        fn allocate_zeroed(&self, layout: Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            (**self).allocate_zeroed(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: Layout) {
+            (**self).deallocate(ptr, layout)
+        }
+
+        unsafe fn grow(
+            &self,
+            ptr: ptr::NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            (**self).grow(ptr, old_layout, new_layout)
+        }
+
+        unsafe fn grow_zeroed(
+            &self,
+            ptr: ptr::NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            (**self).grow_zeroed(ptr, old_layout, new_layout)
+        }
+
+        unsafe fn shrink(
+            &self,
+            ptr: ptr::NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+            (**self).shrink(ptr, old_layout, new_layout)
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::api::Allocator;
+use crate::SlabAllocator;
+use allocator_api2::alloc::{self, AllocError};
+use core::ptr;
+
+// Every method forwards to the crate's own implementation, so slabs still grow in place
+unsafe impl<'m, const N: usize> alloc::Allocator for SlabAllocator<'m, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        Allocator::allocate(self, layout).map_err(|_| AllocError)
+    }
+
+    fn allocate_zeroed(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        Allocator::allocate_zeroed(self, layout).map_err(|_| AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        Allocator::deallocate(self, ptr, layout)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        Allocator::grow(self, ptr, old_layout, new_layout).map_err(|_| AllocError)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        Allocator::grow_zeroed(self, ptr, old_layout, new_layout).map_err(|_| AllocError)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        Allocator::shrink(self, ptr, old_layout, new_layout).map_err(|_| AllocError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn api2_vec() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+
+        let mut v = allocator_api2::vec::Vec::with_capacity_in(2, &allocator);
+        v.extend_from_slice(&[1u16, 2, 3, 4]);
+        assert_eq!(allocator.blocks[0].free_slots(), 7);
+        v.extend_from_slice(&[5; 20]);
+        assert_eq!(allocator.blocks[0].free_slots(), 8);
+        assert_eq!(allocator.blocks[1].free_slots(), 0);
+        assert_eq!(v[..5], [1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::api::{AllocError, Allocator};
+use crate::atomic::{AtomicUsize, Ordering};
+use crate::{sanitize, BufTooSmall};
+use core::{alloc, marker, ptr};
+
+/// The number of words an [`ArraySection`] of `slots` slots tracks them in
+pub const fn words(slots: usize) -> usize {
+    slots.div_ceil(usize::BITS as usize)
+}
+
+/// A section of `SLOTS` slabs of one size, any number rather than one an atomic integer has bits
+/// for, tracked in `WORDS` atomic words where `WORDS` must be [`words(SLOTS)`](words):
+/// ```
+/// # use slab_alloc::array::{words, ArraySection};
+/// let mut buf = [0u8; 100 * 16];
+/// let section = ArraySection::<100, { words(100) }>::new(16, &mut buf[..]).unwrap();
+/// # assert_eq!(section.free_slots(), 100);
+/// ```
+pub struct ArraySection<'m, const SLOTS: usize, const WORDS: usize> {
+    size: usize,
+    allocated: [AtomicUsize; WORDS],
+    memory: ptr::NonNull<[u8]>,
+    _buffer: marker::PhantomData<&'m mut [u8]>,
+}
+
+// The buffer is only accessed through slots claimed in `allocated`, which is only ever modified
+// atomically
+unsafe impl<'m, const SLOTS: usize, const WORDS: usize> Sync for ArraySection<'m, SLOTS, WORDS> {}
+// The section holds the only borrow of its buffer
+unsafe impl<'m, const SLOTS: usize, const WORDS: usize> Send for ArraySection<'m, SLOTS, WORDS> {}
+
+impl<'m, const SLOTS: usize, const WORDS: usize> ArraySection<'m, SLOTS, WORDS> {
+    /// The number of bytes of buffer a section of slabs of `size` bytes needs
+    pub const fn capacity(size: usize) -> usize {
+        SLOTS * size
+    }
+
+    /// Constructor for [`ArraySection`] with slabs of `size` bytes carved from the start of `buf`
+    pub fn new(size: usize, buf: &'m mut [u8]) -> Result<Self, BufTooSmall> {
+        const { assert!(WORDS == words(SLOTS), "WORDS must be words(SLOTS)") };
+        assert!(size != 0, "Sections must have slabs of nonzero size");
+        if buf.len() < Self::capacity(size) {
+            return Err(BufTooSmall {
+                section: 0,
+                required: Self::capacity(size),
+                shortfall: Self::capacity(size) - buf.len(),
+            });
+        }
+        let memory = ptr::NonNull::from(&mut buf[..Self::capacity(size)]);
+        sanitize::created(memory);
+        Ok(Self {
+            size,
+            // The bits past the last slot stay set so they are never handed out
+            allocated: core::array::from_fn(|word| {
+                let slots = SLOTS - word * usize::BITS as usize;
+                AtomicUsize::new(if slots >= usize::BITS as usize {
+                    0
+                } else {
+                    usize::MAX << slots
+                })
+            }),
+            memory,
+            _buffer: marker::PhantomData,
+        })
+    }
+
+    /// The number of slots unallocated
+    pub fn free_slots(&self) -> usize {
+        self.allocated
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed).count_zeros() as usize)
+            .sum()
+    }
+
+    /// The offset of slot `index` from the start of the buffer
+    const fn slot_offset(&self, index: usize) -> usize {
+        index * self.size
+    }
+
+    /// The slot `ptr` points to the start of, if it lies within this section
+    fn slot_of(&self, ptr: ptr::NonNull<u8>) -> Option<usize> {
+        let offset =
+            (ptr.as_ptr() as usize).checked_sub(self.memory.cast::<u8>().as_ptr() as usize)?;
+        (offset < self.memory.len() && offset.is_multiple_of(self.size))
+            .then_some(offset / self.size)
+    }
+
+    /// Whether `ptr` points to a slab of this section, allocated or not
+    pub fn owns(&self, ptr: ptr::NonNull<u8>) -> bool {
+        self.slot_of(ptr).is_some()
+    }
+
+    /// The alignment every slab of the section has
+    fn slot_align(&self) -> usize {
+        let start = self.memory.cast::<u8>().as_ptr() as usize;
+        1 << (start | self.size).trailing_zeros()
+    }
+}
+
+#[cfg(feature = "sanitize")]
+impl<'m, const SLOTS: usize, const WORDS: usize> Drop for ArraySection<'m, SLOTS, WORDS> {
+    fn drop(&mut self) {
+        sanitize::destroyed(self.memory);
+    }
+}
+
+unsafe impl<'m, const SLOTS: usize, const WORDS: usize> Allocator
+    for ArraySection<'m, SLOTS, WORDS>
+{
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        if self.size < layout.pad_to_align().size() || self.slot_align() < layout.align() {
+            return Err(AllocError);
+        }
+        // Set the lowest clear bit of the first word with one
+        let index = self
+            .allocated
+            .iter()
+            .enumerate()
+            .find_map(|(word, bits)| {
+                let load = bits
+                    .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                        (load != !0).then(|| load | (!load & load.wrapping_add(1)))
+                    })
+                    .ok()?;
+                Some(word * usize::BITS as usize + (!load).trailing_zeros() as usize)
+            })
+            .ok_or(AllocError)?;
+
+        // SAFETY: the slot is within the section, which lies within the buffer
+        let start = unsafe { self.memory.cast::<u8>().add(self.slot_offset(index)) };
+        let slab = ptr::NonNull::slice_from_raw_parts(start, self.size);
+        sanitize::allocated(self.memory, slab);
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, _layout: alloc::Layout) {
+        let index = self
+            .slot_of(ptr)
+            .expect("Could not deallocate slab: ptr is not a slab of this section");
+        let bit = 1 << (index % usize::BITS as usize);
+        let word = &self.allocated[index / usize::BITS as usize];
+
+        sanitize::freed(
+            self.memory,
+            ptr::NonNull::slice_from_raw_parts(ptr, self.size),
+        );
+        assert!(
+            word.fetch_and(!bit, Ordering::Release) & bit != 0,
+            "Could not deallocate block"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::Aligned;
+    use core::alloc::Layout;
+
+    #[test]
+    fn array_section() {
+        assert!(ArraySection::<100, { words(100) }>::new(8, &mut [0u8; 799][..]).is_err());
+        let mut buf = Aligned([0u8; 100 * 8]);
+        let section = ArraySection::<100, { words(100) }>::new(8, &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        assert!(section.allocate(Layout::new::<[u8; 9]>()).is_err());
+
+        let slabs: [_; 100] = core::array::from_fn(|_| section.allocate(layout).unwrap());
+        assert!(section.allocate(layout).is_err());
+        assert!(section.owns(slabs[99].cast()));
+        unsafe { section.deallocate(slabs[70].cast(), layout) };
+        assert_eq!(section.free_slots(), 1);
+        assert_eq!(section.allocate(layout).unwrap(), slabs[70]);
+        for slab in slabs {
+            unsafe { section.deallocate(slab.cast(), layout) };
+        }
+        assert_eq!(section.free_slots(), 100);
+    }
+}
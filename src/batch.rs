@@ -0,0 +1,119 @@
+use crate::dealloc::DeallocError;
+use crate::section::{Bitmap, Section};
+use crate::{dangling, SlabAllocator};
+use core::{alloc, ptr};
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Allocates slabs able to hold `layout` into `out` until it is full or no section able to
+    /// hold them has any left, returning how many were allocated. Each section they are taken
+    /// from is claimed with a single atomic operation rather than one per slab, for packet pools
+    /// and object caches that refill in bursts
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_many(&self, layout: alloc::Layout, out: &mut [ptr::NonNull<[u8]>]) -> usize {
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            out.fill(dangling(layout));
+            return out.len();
+        }
+        let mut count = 0;
+        for section in self.blocks.iter() {
+            if count == out.len() {
+                break;
+            }
+            if !self.fit_policy.accepts(section, layout) {
+                continue;
+            }
+            let wanted = u32::try_from(out.len() - count).unwrap_or(u32::MAX);
+            let mut slots = section.allocate_batch(wanted);
+            while slots != 0 {
+                let slab = self.claimed(section, slots.trailing_zeros());
+                self.served(layout, slab);
+                out[count] = slab;
+                count += 1;
+                slots &= slots - 1;
+            }
+        }
+        if count < out.len() {
+            self.failed(layout);
+        }
+        count
+    }
+
+    /// Frees every slab in `slabs`, all allocated with `layout`, freeing successive slabs of the
+    /// same section with a single atomic operation rather than one per slab. Pointers that
+    /// cannot be freed are handled by the [`DeallocPolicy`](crate::DeallocPolicy)
+    ///
+    /// # Safety
+    /// Every slab must have been allocated by this allocator with `layout`, appear once and not
+    /// be used afterwards
+    pub unsafe fn deallocate_many(&self, slabs: &[ptr::NonNull<u8>], layout: alloc::Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        // The section whose slabs are being gathered, the slots gathered and how many
+        // allocations they make up
+        let mut pending: Option<(&Section, Bitmap, u32)> = None;
+        for &ptr in slabs {
+            let Some((index, section, slot)) = self.locate_for(ptr, layout) else {
+                self.invalid_free(ptr, DeallocError::Foreign);
+                continue;
+            };
+            if let Some((gathered, mask, allocations)) = pending {
+                if !ptr::eq(gathered, section) {
+                    self.release_gathered(gathered, mask, allocations);
+                    pending = None;
+                }
+            }
+            let (_, mask, allocations) = pending.get_or_insert((section, 0, 0));
+            if !section.is_allocated(slot) || *mask & 1 << slot != 0 {
+                self.invalid_free(ptr, DeallocError::NotAllocated);
+                continue;
+            }
+            let run = section.run_of(slot);
+            let mut slots = run;
+            while slots != 0 {
+                self.releasing(section, slots.trailing_zeros());
+                slots &= slots - 1;
+            }
+            *mask |= run;
+            *allocations += 1;
+            self.hook_deallocated(layout, index, slot);
+        }
+        if let Some((section, mask, allocations)) = pending {
+            self.release_gathered(section, mask, allocations);
+        }
+    }
+
+    /// Frees the slots of `section` in `mask`, making up `allocations` allocations that have
+    /// already been released
+    unsafe fn release_gathered(&self, section: &Section, mask: Bitmap, allocations: u32) {
+        section.deallocate_batch(mask);
+        for _ in 0..allocations {
+            self.released(section);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::ptr::NonNull;
+
+    #[test]
+    fn many() {
+        let mut buf = test::buffer();
+        let allocator = SlabAllocator::new(test::sections(0b1), &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let mut slabs = [NonNull::slice_from_raw_parts(NonNull::dangling(), 0); 10];
+
+        assert_eq!(allocator.allocate_many(layout, &mut slabs[..4]), 4);
+        assert_eq!(allocator.allocate_many(layout, &mut slabs[4..]), 4);
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [8, 1]);
+        assert_eq!(slabs[7].len(), 64);
+
+        let ptrs: [NonNull<u8>; 8] = core::array::from_fn(|index| slabs[index].cast());
+        unsafe { allocator.deallocate_many(&ptrs, layout) };
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [1, 0]);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::error::SlabError;
+use crate::{dangling, SlabAllocator};
+use core::{alloc, hint, ptr};
+
+/// The most spins waited between two attempts, however many have failed
+const MAX_BACKOFF: u32 = 1 << 10;
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Allocates a slab like [`try_allocate`](SlabAllocator::try_allocate), but if every
+    /// section able to hold `layout` is full retries up to `max_spins` times, spinning twice as
+    /// long after each failure, for producers and consumers on other cores that briefly exhaust
+    /// a section. Only the final failure is reported
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_blocking(
+        &self,
+        layout: alloc::Layout,
+        max_spins: u32,
+    ) -> Result<ptr::NonNull<[u8]>, SlabError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        if self.section_for_layout(layout).is_some() {
+            let mut backoff = 1;
+            for _ in 0..max_spins {
+                if let Ok(slab) = self.take_slab(layout) {
+                    self.served(layout, slab);
+                    return Ok(slab);
+                }
+                for _ in 0..backoff {
+                    hint::spin_loop();
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+        self.try_allocate(layout)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    fn allocate_blocking() {
+        let mut buf = Aligned([0u8; 64]);
+        let allocator =
+            SlabAllocator::new([Section::new(64, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert_eq!(
+            allocator.allocate_blocking(layout, 4),
+            Err(SlabError::SectionFull { index: 0 })
+        );
+
+        assert_eq!(slab, allocator.blocks[0].slot_ptr(0));
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let slab = allocator.blocks[0].slot_ptr(0);
+                unsafe { allocator.deallocate(slab.cast(), layout) };
+            });
+            assert!(allocator.allocate_blocking(layout, u32::MAX).is_ok());
+        });
+    }
+}
@@ -0,0 +1,114 @@
+extern crate alloc;
+
+use crate::api::AllocError;
+use crate::section::Section;
+use crate::{BufferOwner, CreateError, SlabAllocator};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ptr;
+
+/// Frees the boxed buffer `memory` was made from with [`Box::into_raw`]
+///
+/// # Safety
+/// `memory` must come from a boxed slice, and no reference into it may be used afterwards
+pub(crate) unsafe fn free_box(memory: ptr::NonNull<[u8]>) {
+    drop(Box::from_raw(memory.as_ptr()));
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Moves `value` into a slab of the first section able to hold it.
+    /// Calls [`handle_alloc_error`](alloc::alloc::handle_alloc_error) if there is none free
+    pub fn boxed<T>(&self, value: T) -> Box<T, &Self> {
+        Box::new_in(value, self)
+    }
+
+    /// Moves `value` into a slab of the first section able to hold it, failing if there is
+    /// none free
+    pub fn try_boxed<T>(&self, value: T) -> Result<Box<T, &Self>, AllocError> {
+        Box::try_new_in(value, self)
+    }
+
+    /// An empty vector allocating from the allocator, with room for `capacity` elements
+    pub fn vec_with_capacity<T>(&self, capacity: usize) -> Vec<T, &Self> {
+        Vec::with_capacity_in(capacity, self)
+    }
+}
+
+impl<const N: usize> SlabAllocator<'static, N> {
+    /// Constructor for [`SlabAllocator`] owning its buffer, such as a `Vec<u8>` or boxed slice,
+    /// so it can be stored in long-lived structs. The buffer is freed when the allocator is
+    /// dropped. Otherwise as [`new`](SlabAllocator::new)
+    pub fn from_box(blocks: [Section; N], buf: impl Into<Box<[u8]>>) -> Result<Self, CreateError> {
+        let memory = Box::into_raw(buf.into());
+        // SAFETY: the box is only freed when the allocator is dropped or here, if it is not used
+        unsafe {
+            match Self::over(blocks, ptr::NonNull::new_unchecked(memory)) {
+                Ok(mut allocator) => {
+                    allocator.buffer_owner = BufferOwner::Boxed;
+                    Ok(allocator)
+                }
+                Err(error) => {
+                    drop(Box::from_raw(memory));
+                    Err(error)
+                }
+            }
+        }
+    }
+
+    /// Moves the allocator to the heap for good, returning a `'static` handle to it for APIs
+    /// that need an `Allocator + 'static`, such as spawning into executors
+    pub fn leak(self) -> &'static Self {
+        Box::leak(Box::new(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Box;
+    use crate::test::Aligned;
+    use crate::*;
+    use core::ptr::{self, NonNull};
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn boxed() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+
+        let section = |ptr: *const u8| {
+            let ptr = NonNull::new(ptr.cast_mut()).unwrap();
+            allocator.layout_of(ptr).unwrap().section
+        };
+        let value = allocator.boxed(7u64);
+        assert_eq!(*value, 7);
+        assert_eq!(section(ptr::from_ref(&*value).cast()), 0);
+
+        let mut vec = allocator.vec_with_capacity::<u16>(20);
+        vec.extend(0..20);
+        assert_eq!(section(vec.as_ptr().cast()), 1);
+        assert!(allocator.try_boxed([0u8; 64]).is_err());
+    }
+
+    #[test]
+    fn from_box() {
+        extern crate alloc;
+        let section = || Section::new(8, AtomicU8::new(0)).with_align(8);
+        // Room for the slabs wherever the vector starts
+        let allocator = SlabAllocator::from_box([section()], alloc::vec![0u8; 64 + 7]).unwrap();
+        let value = allocator.boxed(1u64);
+        assert_eq!(*value, 1);
+        drop(value);
+        assert!(SlabAllocator::from_box([section()], alloc::vec![0u8; 8]).is_err());
+    }
+
+    #[test]
+    fn leak() {
+        let buf = Box::leak(Box::new(Aligned([0u8; 64])));
+        let allocator: &'static SlabAllocator<'static, 1> =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..])
+                .unwrap()
+                .leak();
+        let value = allocator.boxed(1u32);
+        assert_eq!(*value, 1);
+    }
+}
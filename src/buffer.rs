@@ -0,0 +1,111 @@
+use crate::api::{AllocError, Allocator};
+use crate::section::Result;
+use crate::SlabAllocator;
+use core::alloc;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+/// A fixed length byte buffer occupying a single slab, for use as a DMA or packet buffer.
+/// The slab is returned to the allocator when the buffer is dropped
+pub struct SlabBuffer<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    ptr: ptr::NonNull<u8>,
+    len: usize,
+}
+
+impl<'a, 'm, const N: usize> SlabBuffer<'a, 'm, N> {
+    /// Allocates a buffer of `len` bytes from `allocator`
+    pub fn new(allocator: &'a SlabAllocator<'m, N>, len: usize) -> Result<Self> {
+        let layout = alloc::Layout::array::<u8>(len).map_err(|_| AllocError)?;
+        let ptr = allocator.allocate(layout)?.cast();
+        Ok(Self {
+            allocator,
+            ptr,
+            len,
+        })
+    }
+}
+
+impl<'a, 'm, const N: usize> Deref for SlabBuffer<'a, 'm, N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the slab is at least `len` bytes and owned by this buffer until dropped
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<'a, 'm, const N: usize> DerefMut for SlabBuffer<'a, 'm, N> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the slab is at least `len` bytes and owned by this buffer until dropped
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<'a, 'm, const N: usize> Drop for SlabBuffer<'a, 'm, N> {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` was allocated by `allocator` with this layout
+        unsafe {
+            self.allocator
+                .deallocate(self.ptr, alloc::Layout::array::<u8>(self.len).unwrap())
+        }
+    }
+}
+
+// The slab does not move when the buffer does, and is only freed on drop
+#[cfg(feature = "embedded-dma")]
+unsafe impl<'a, 'm, const N: usize> embedded_dma::ReadBuffer for SlabBuffer<'a, 'm, N> {
+    type Word = u8;
+
+    unsafe fn read_buffer(&self) -> (*const u8, usize) {
+        (self.ptr.as_ptr(), self.len)
+    }
+}
+
+#[cfg(feature = "embedded-dma")]
+unsafe impl<'a, 'm, const N: usize> embedded_dma::WriteBuffer for SlabBuffer<'a, 'm, N> {
+    type Word = u8;
+
+    unsafe fn write_buffer(&mut self) -> (*mut u8, usize) {
+        (self.ptr.as_ptr(), self.len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn distinct_buffers() {
+        let mut buf = [0u8; 128];
+        let allocator = SlabAllocator::new([Section::new(16, AtomicU8::new(0))], &mut buf[..])
+            .expect("Creation of allocator failed");
+
+        let mut a = SlabBuffer::new(&allocator, 16).unwrap();
+        let mut b = SlabBuffer::new(&allocator, 12).unwrap();
+        a.fill(0xAA);
+        b.fill(0x55);
+        assert!(a.iter().all(|&x| x == 0xAA));
+        assert!(b.iter().all(|&x| x == 0x55));
+        assert_eq!(b.len(), 12);
+        assert!(SlabBuffer::new(&allocator, 17).is_err());
+
+        drop(a);
+        assert_eq!(allocator.blocks[0].free_slots(), 7);
+    }
+
+    #[cfg(feature = "embedded-dma")]
+    #[test]
+    fn dma_buffer() {
+        use embedded_dma::{ReadBuffer, WriteBuffer};
+        let mut buf = [0u8; 128];
+        let allocator = SlabAllocator::new([Section::new(16, AtomicU8::new(0))], &mut buf[..])
+            .expect("Creation of allocator failed");
+
+        let mut a = SlabBuffer::new(&allocator, 10).unwrap();
+        let (write, len) = unsafe { a.write_buffer() };
+        assert_eq!(len, 10);
+        assert_eq!(unsafe { a.read_buffer() }, (write as *const u8, 10));
+    }
+}
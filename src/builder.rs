@@ -0,0 +1,213 @@
+use crate::section::{Atomics, Section, INVALID_SLOT_COUNT};
+use crate::SlabAllocator;
+use core::fmt;
+
+/// Why a [`SlabAllocatorBuilder`] could not build an allocator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BuildError {
+    /// More sections were added than the allocator has
+    TooManySections {
+        /// The number of sections of the allocator
+        expected: usize,
+    },
+    /// Fewer sections were added than the allocator has
+    TooFewSections {
+        /// The number of sections of the allocator
+        expected: usize,
+        /// The number of sections added
+        found: usize,
+    },
+    /// A section has slabs of size zero
+    ZeroSize,
+    /// A section's slot count is not one of 1, 8, 16, 32, 64 or, with the `u128` feature, 128
+    InvalidSlotCount {
+        /// The slab size of the section
+        size: usize,
+        /// The slot count given
+        slots: u32,
+    },
+    /// The buffer cannot hold every section
+    BufTooSmall {
+        /// The number of bytes the sections need
+        required: usize,
+        /// The length of the buffer given
+        available: usize,
+    },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManySections { expected } => {
+                write!(f, "more than {expected} sections added")
+            }
+            Self::TooFewSections { expected, found } => {
+                write!(f, "{found} sections added but {expected} expected")
+            }
+            Self::ZeroSize => write!(f, "section with slabs of size zero"),
+            Self::InvalidSlotCount { size, slots } => write!(
+                f,
+                "section of size {size} has {slots} slots; {INVALID_SLOT_COUNT}"
+            ),
+            Self::BufTooSmall {
+                required,
+                available,
+            } => write!(f, "buffer of {available} bytes but {required} required"),
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+/// Builder for a [`SlabAllocator`] with `N` sections, added in any order:
+/// ```
+/// # use slab_alloc::SlabAllocatorBuilder;
+/// let mut buf = [0u8; 4096];
+/// let allocator = SlabAllocatorBuilder::new()
+///     .section(128, 16)
+///     .section(32, 64)
+///     .build(&mut buf[..])
+///     .unwrap();
+/// # let _: slab_alloc::SlabAllocator<2> = allocator;
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SlabAllocatorBuilder<const N: usize> {
+    sections: [(usize, u32); N],
+    added: usize,
+}
+
+impl<const N: usize> Default for SlabAllocatorBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> SlabAllocatorBuilder<N> {
+    /// A builder with no sections
+    pub const fn new() -> Self {
+        Self {
+            sections: [(0, 0); N],
+            added: 0,
+        }
+    }
+
+    /// Adds a section of `slots` slabs of `size` bytes
+    pub const fn section(mut self, size: usize, slots: u32) -> Self {
+        if self.added < N {
+            self.sections[self.added] = (size, slots);
+        }
+        self.added += 1;
+        self
+    }
+
+    /// Checks the sections added, returning them sorted by size
+    fn validate(&self) -> Result<[(usize, u32); N], BuildError> {
+        if self.added > N {
+            return Err(BuildError::TooManySections { expected: N });
+        }
+        if self.added < N {
+            return Err(BuildError::TooFewSections {
+                expected: N,
+                found: self.added,
+            });
+        }
+        for &(size, slots) in self.sections.iter() {
+            if size == 0 {
+                return Err(BuildError::ZeroSize);
+            }
+            if Atomics::with_slots(slots).is_none() {
+                return Err(BuildError::InvalidSlotCount { size, slots });
+            }
+        }
+        let mut sections = self.sections;
+        sections.sort_unstable_by_key(|&(size, _)| size);
+        Ok(sections)
+    }
+
+    /// The number of bytes of buffer the sections need
+    pub fn required_size(&self) -> Result<usize, BuildError> {
+        Ok(self
+            .validate()?
+            .iter()
+            .map(|&(size, slots)| size * slots as usize)
+            .sum())
+    }
+
+    /// Builds the allocator, its sections in order of size, from `buf`
+    pub fn build<'m>(&self, buf: &'m mut [u8]) -> Result<SlabAllocator<'m, N>, BuildError> {
+        let required = self.required_size()?;
+        if buf.len() < required {
+            return Err(BuildError::BufTooSmall {
+                required,
+                available: buf.len(),
+            });
+        }
+        let sections = self.validate()?;
+        let blocks = core::array::from_fn(|i| {
+            let (size, slots) = sections[i];
+            Section::new(
+                size,
+                Atomics::with_slots(slots).expect("Slot count validated"),
+            )
+        });
+        Ok(SlabAllocator::new(blocks, buf).expect("Buffer size validated"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn build() {
+        let mut buf = [0u8; 8 * 64 + 32 * 8];
+        let builder = SlabAllocatorBuilder::new().section(32, 8).section(8, 64);
+        assert_eq!(builder.required_size(), Ok(8 * 64 + 32 * 8));
+        {
+            let allocator: SlabAllocator<2> = builder.build(&mut buf[..]).unwrap();
+            assert_eq!(allocator.blocks[0].size, 8);
+            assert_eq!(allocator.blocks[1].total_slots(), 8);
+        }
+
+        assert_eq!(
+            builder.build(&mut buf[1..]).err(),
+            Some(BuildError::BufTooSmall {
+                required: 8 * 64 + 32 * 8,
+                available: 8 * 64 + 32 * 8 - 1,
+            })
+        );
+        assert_eq!(
+            SlabAllocatorBuilder::<2>::new()
+                .section(8, 8)
+                .build(&mut buf[..])
+                .err(),
+            Some(BuildError::TooFewSections {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            SlabAllocatorBuilder::<1>::new()
+                .section(8, 8)
+                .section(16, 8)
+                .build(&mut buf[..])
+                .err(),
+            Some(BuildError::TooManySections { expected: 1 })
+        );
+        assert_eq!(
+            SlabAllocatorBuilder::<1>::new()
+                .section(8, 12)
+                .build(&mut buf[..])
+                .err(),
+            Some(BuildError::InvalidSlotCount { size: 8, slots: 12 })
+        );
+        assert_eq!(
+            SlabAllocatorBuilder::<1>::new()
+                .section(0, 8)
+                .build(&mut buf[..])
+                .err(),
+            Some(BuildError::ZeroSize)
+        );
+    }
+}
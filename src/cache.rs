@@ -0,0 +1,206 @@
+use crate::api::AllocError;
+use crate::atomic::Ordering;
+use crate::section::{AtomicBitmap, Atomics, Section};
+use crate::{place, BufTooSmall};
+use core::{marker, mem, ops, ptr};
+
+/// Cache of objects of type `T` kept in a single section, in the manner of Bonwick's object
+/// caches. A slot's object is built with the constructor the first time the slot is handed out
+/// and, if [`keep_constructed`](ObjectCache::keep_constructed), left constructed when returned
+/// so the next user gets it without rebuilding it. Objects still constructed are dropped with
+/// the cache
+pub struct ObjectCache<'m, T> {
+    section: Section,
+    constructed: AtomicBitmap,
+    keep: bool,
+    constructor: fn() -> T,
+    _buffer: marker::PhantomData<&'m mut [u8]>,
+    _objects: marker::PhantomData<T>,
+}
+
+// Objects are only reached through the slot claimed in the section, so each is used by one
+// thread at a time
+unsafe impl<'m, T: Send> Sync for ObjectCache<'m, T> {}
+
+impl<'m, T> ObjectCache<'m, T> {
+    /// Constructor for [`ObjectCache`] where
+    /// `quantity` is the number of objects the cache holds,
+    /// `buf` is the memory they are kept in and
+    /// `constructor` builds an object for a slot that holds none
+    pub fn new<A: Into<Atomics>>(
+        quantity: A,
+        buf: &'m mut [u8],
+        constructor: fn() -> T,
+    ) -> Result<Self, BufTooSmall> {
+        let mut section = Section {
+            align: mem::align_of::<T>(),
+            ..Section::new(mem::size_of::<T>(), quantity)
+        };
+        place(core::slice::from_mut(&mut section), ptr::NonNull::from(buf))?;
+        Ok(Self {
+            section,
+            constructed: AtomicBitmap::new(0),
+            keep: false,
+            constructor,
+            _buffer: marker::PhantomData,
+            _objects: marker::PhantomData,
+        })
+    }
+
+    /// Leaves returned objects constructed so they can be handed out again as they are.
+    /// Users must return objects in a state fit for the next user
+    pub fn keep_constructed(mut self) -> Self {
+        self.keep = true;
+        self
+    }
+
+    /// The section the objects are kept in
+    pub fn section(&self) -> &Section {
+        &self.section
+    }
+
+    /// Hands out an object, constructing it unless its slot still holds one
+    pub fn get(&self) -> Result<Cached<'_, 'm, T>, AllocError> {
+        let slot = self.section.allocate()?;
+        let object = self.section.slot_ptr(slot).cast::<T>();
+        if self.constructed.fetch_and(!(1 << slot), Ordering::Acquire) & (1 << slot) == 0 {
+            // SAFETY: the slot was just claimed and holds no object
+            unsafe { object.write((self.constructor)()) };
+        }
+        Ok(Cached {
+            cache: self,
+            slot,
+            object,
+        })
+    }
+
+    /// Takes back the object in `slot`, dropping it unless objects are kept constructed
+    fn release(&self, slot: u32, object: ptr::NonNull<T>) {
+        if self.keep {
+            self.constructed.fetch_or(1 << slot, Ordering::Release);
+        } else {
+            // SAFETY: the object is no longer borrowed
+            unsafe { object.drop_in_place() };
+        }
+        self.section
+            .deallocate(slot)
+            .expect("Could not return object to cache");
+    }
+}
+
+impl<'m, T> Drop for ObjectCache<'m, T> {
+    fn drop(&mut self) {
+        let constructed = *self.constructed.get_mut();
+        for slot in (0..self.section.total_slots()).filter(|slot| constructed & (1 << slot) != 0) {
+            // SAFETY: the slot holds a constructed object nothing borrows
+            unsafe { self.section.slot_ptr(slot).cast::<T>().drop_in_place() };
+        }
+    }
+}
+
+/// An object handed out by an [`ObjectCache`], returned to it when dropped
+pub struct Cached<'c, 'm, T> {
+    cache: &'c ObjectCache<'m, T>,
+    slot: u32,
+    object: ptr::NonNull<T>,
+}
+
+impl<T> ops::Deref for Cached<'_, '_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the object is constructed and exclusively held until the guard is dropped
+        unsafe { self.object.as_ref() }
+    }
+}
+
+impl<T> ops::DerefMut for Cached<'_, '_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the object is constructed and exclusively held until the guard is dropped
+        unsafe { self.object.as_mut() }
+    }
+}
+
+impl<T> Drop for Cached<'_, '_, T> {
+    fn drop(&mut self) {
+        self.cache.release(self.slot, self.object);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+    static BUILT: AtomicUsize = AtomicUsize::new(0);
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Object(u32);
+
+    impl Drop for Object {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn build() -> Object {
+        BUILT.fetch_add(1, Ordering::Relaxed);
+        Object(7)
+    }
+
+    #[test]
+    fn object_cache() {
+        let mut buf = Aligned([0u8; 4 * 8]);
+        let cache = ObjectCache::new(AtomicU8::new(0), &mut buf.0[..], build).unwrap();
+        {
+            let mut object = cache.get().unwrap();
+            assert_eq!(object.0, 7);
+            object.0 = 8;
+        }
+        assert_eq!(cache.get().unwrap().0, 7);
+        assert_eq!(
+            (
+                BUILT.load(Ordering::Relaxed),
+                DROPPED.load(Ordering::Relaxed)
+            ),
+            (2, 2)
+        );
+
+        let cache = cache.keep_constructed();
+        let objects: [_; 8] = core::array::from_fn(|_| cache.get().unwrap());
+        assert!(cache.get().is_err());
+        drop(objects);
+        assert_eq!(cache.get().unwrap().0, 7);
+        assert_eq!(
+            (
+                BUILT.load(Ordering::Relaxed),
+                DROPPED.load(Ordering::Relaxed)
+            ),
+            (10, 2)
+        );
+        drop(cache);
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    #[cfg(feature = "u128")]
+    fn wide_section() {
+        let mut buf = Aligned([0u8; 4 * 128]);
+        let quantity = portable_atomic::AtomicU128::new(0);
+        let cache = ObjectCache::new(quantity, &mut buf.0[..], || 5u32)
+            .unwrap()
+            .keep_constructed();
+        let mut objects: [_; 128] = core::array::from_fn(|_| cache.get().unwrap());
+        for (value, object) in objects.iter_mut().enumerate() {
+            **object = value as u32;
+        }
+        drop(objects);
+        // Every slot, including the upper 64, kept its object
+        let objects: [_; 128] = core::array::from_fn(|_| cache.get().unwrap());
+        assert!(objects
+            .iter()
+            .enumerate()
+            .all(|(value, object)| **object == value as u32));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::api::{AllocError, Allocator};
+use crate::section::MAX_SLOTS;
+use crate::SlabAllocator;
+use core::alloc;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The layout a slot was last allocated with
+struct Recorded {
+    size: AtomicUsize,
+    align: AtomicUsize,
+}
+
+impl Recorded {
+    const fn new() -> Self {
+        Self {
+            size: AtomicUsize::new(0),
+            align: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Debugging allocator wrapping a [`SlabAllocator`] that records the layout of every allocation
+/// and panics if a different layout is passed to `deallocate`, `grow` or `shrink`. Zero sized
+/// allocations, which take no slab, and slabs of sections added since the allocator was created
+/// are not checked
+pub struct LayoutChecked<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    layouts: [[Recorded; MAX_SLOTS]; N],
+}
+
+impl<'a, 'm, const N: usize> LayoutChecked<'a, 'm, N> {
+    /// Wraps `allocator`
+    pub fn new(allocator: &'a SlabAllocator<'m, N>) -> Self {
+        Self {
+            allocator,
+            layouts: [const { [const { Recorded::new() }; MAX_SLOTS] }; N],
+        }
+    }
+
+    /// Where the layout of the slab at `ptr`, allocated with a layout of the size of `layout`, is
+    /// recorded, if it is
+    fn recorded(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) -> Option<&Recorded> {
+        if layout.size() == 0 {
+            return None;
+        }
+        let (index, _, slot) = self
+            .allocator
+            .locate(ptr)
+            .expect("Pointer was not allocated by this allocator");
+        Some(&self.layouts.get(index)?[slot as usize])
+    }
+
+    fn record(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        let Some(recorded) = self.recorded(ptr, layout) else {
+            return;
+        };
+        recorded.size.store(layout.size(), Ordering::Relaxed);
+        recorded.align.store(layout.align(), Ordering::Relaxed);
+    }
+
+    fn check(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        let Some(recorded) = self.recorded(ptr, layout) else {
+            return;
+        };
+        let size = recorded.size.load(Ordering::Relaxed);
+        let align = recorded.align.load(Ordering::Relaxed);
+        assert!(
+            size == layout.size() && align == layout.align(),
+            "Slab allocated with size {} align {} but passed size {} align {}",
+            size,
+            align,
+            layout.size(),
+            layout.align()
+        );
+    }
+}
+
+unsafe impl<'a, 'm, const N: usize> Allocator for LayoutChecked<'a, 'm, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let slab = self.allocator.allocate(layout)?;
+        self.record(slab.cast(), layout);
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        self.check(ptr, layout);
+        self.allocator.deallocate(ptr, layout);
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.check(ptr, old_layout);
+        let slab = self.allocator.grow(ptr, old_layout, new_layout)?;
+        self.record(slab.cast(), new_layout);
+        Ok(slab)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.check(ptr, old_layout);
+        let slab = self.allocator.grow_zeroed(ptr, old_layout, new_layout)?;
+        self.record(slab.cast(), new_layout);
+        Ok(slab)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.check(ptr, old_layout);
+        let slab = self.allocator.shrink(ptr, old_layout, new_layout)?;
+        self.record(slab.cast(), new_layout);
+        Ok(slab)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn matching_layouts() {
+        extern crate std;
+        let mut buf = Aligned([0u8; 256]);
+        let allocator =
+            SlabAllocator::new([Section::new(32, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let checked = LayoutChecked::new(&allocator);
+
+        let mut v = std::vec::Vec::with_capacity_in(2, &checked);
+        v.extend_from_slice(&[1u16, 2, 3, 4, 5]);
+        v.shrink_to_fit();
+        assert_eq!(v, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn unchecked_slabs() {
+        let mut buf = Aligned([0u8; 32]);
+        let mut bank = Aligned([0u8; 4096]);
+        let allocator =
+            SlabAllocator::new([Section::new(32, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let checked = LayoutChecked::new(&allocator);
+        let layout = Layout::new::<u64>();
+        let empty = Layout::new::<()>();
+
+        let slab = checked.allocate(layout).unwrap();
+        unsafe {
+            let shrunk = checked.shrink(slab.cast(), layout, empty).unwrap();
+            checked.deallocate(shrunk.cast(), empty);
+        }
+        let zst = checked.allocate(empty).unwrap();
+        unsafe { checked.deallocate(zst.cast(), empty) };
+
+        // Slabs of added sections are handed out and freed without being checked
+        checked.allocate(layout).unwrap();
+        allocator
+            .add_region(Section::new(32, AtomicBool::new(false)), &mut bank.0[..])
+            .unwrap();
+        let added = checked.allocate(layout).unwrap();
+        unsafe { checked.deallocate(added.cast(), layout) };
+    }
+
+    #[test]
+    #[should_panic(expected = "Slab allocated with size 8 align 8 but passed size 4 align 4")]
+    fn mismatched_layout() {
+        let mut buf = Aligned([0u8; 256]);
+        let allocator =
+            SlabAllocator::new([Section::new(32, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let checked = LayoutChecked::new(&allocator);
+
+        let ptr = checked.allocate(Layout::new::<u64>()).unwrap();
+        unsafe { checked.deallocate(ptr.cast(), Layout::new::<u32>()) };
+    }
+}
@@ -0,0 +1,112 @@
+use crate::section::{Bitmap, Section};
+use crate::SlabAllocator;
+
+/// The allocation state of an allocator's sections, taken with
+/// [`checkpoint`](SlabAllocator::checkpoint)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Snapshot<const N: usize> {
+    bitmaps: [Bitmap; N],
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Records which slabs are allocated, so everything allocated after it can be freed at once
+    /// with [`restore`](SlabAllocator::restore). Sections added since the allocator was created
+    /// are not recorded
+    pub fn checkpoint(&self) -> Snapshot<N> {
+        Snapshot {
+            bitmaps: core::array::from_fn(|index| self.blocks[index].bitmap()),
+        }
+    }
+
+    /// Frees every slab that was free when `snapshot` was taken, returning how many were freed.
+    /// Slabs freed since stay free, while one allocated at the checkpoint that was freed and
+    /// handed out again since is kept
+    ///
+    /// # Safety
+    /// None of the slabs allocated since the snapshot may be used afterwards, including slabs
+    /// held in [`Magazines`](crate::Magazines) in front of the allocator, and no allocation or
+    /// free may run at the same time
+    pub unsafe fn restore(&self, snapshot: &Snapshot<N>) -> usize {
+        self.blocks
+            .iter()
+            .zip(snapshot.bitmaps)
+            .map(|(section, saved)| self.release_slots(section, section.bitmap() & !saved))
+            .sum()
+    }
+
+    /// Frees every slab, including those of sections added since the allocator was created, so
+    /// it can be reused between iterations of a main loop. Returns how many were freed
+    ///
+    /// # Safety
+    /// No slab may be used afterwards, including slabs held in [`Magazines`](crate::Magazines)
+    /// in front of the allocator, and no allocation or free may run at the same time
+    pub unsafe fn reset(&self) -> usize {
+        self.blocks
+            .iter()
+            .chain(self.extra())
+            .map(|section| self.release_slots(section, section.bitmap()))
+            .sum()
+    }
+
+    /// Frees the slots of `section` in `mask`, all of which must be allocated and unused,
+    /// returning how many were freed
+    unsafe fn release_slots(&self, section: &Section, mask: Bitmap) -> usize {
+        let mut slots = mask;
+        while slots != 0 {
+            self.releasing(section, slots.trailing_zeros());
+            slots &= slots - 1;
+        }
+        section.deallocate_batch(mask);
+        for _ in 0..mask.count_ones() {
+            self.released(section);
+        }
+        mask.count_ones() as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn checkpoint() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        let layout = Layout::new::<u64>();
+        let kept = allocator.allocate(layout).unwrap();
+
+        let snapshot = allocator.checkpoint();
+        let first = allocator.allocate(layout).unwrap();
+        for _ in 0..2 {
+            allocator.allocate(layout).unwrap();
+        }
+        allocator.allocate(Layout::new::<[u8; 64]>()).unwrap();
+
+        assert_eq!(unsafe { allocator.restore(&snapshot) }, 4);
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [1, 0]);
+        assert_eq!(allocator.allocate(layout).unwrap(), first);
+        unsafe {
+            allocator.deallocate(kept.cast(), layout);
+            allocator.deallocate(first.cast(), layout);
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let first = allocator.allocate(layout).unwrap();
+        allocator.allocate(layout).unwrap();
+
+        assert_eq!(unsafe { allocator.reset() }, 2);
+        assert_eq!(allocator.gauges()[0].get(), 0);
+        assert_eq!(allocator.allocate(layout).unwrap(), first);
+        unsafe { allocator.deallocate(first.cast(), layout) };
+    }
+}
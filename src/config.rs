@@ -0,0 +1,293 @@
+use crate::section::{slots_for, Atomics, Section, INVALID_SLOT_COUNT};
+use core::alloc::Layout;
+use core::mem;
+
+/// A compile time description of the sections of an allocator, usable from const contexts:
+/// ```
+/// # use slab_alloc::SlabConfig;
+/// struct Config;
+/// impl SlabConfig<2> for Config {
+///     const SIZES: [usize; 2] = [16, 128];
+///     const SLOTS: [u32; 2] = [64, 8];
+/// }
+/// let handles: [Option<u32>; Config::TOTAL_SLOTS] = [None; 72];
+/// let buffer = [0u8; Config::TOTAL_BYTES];
+/// ```
+pub trait SlabConfig<const N: usize> {
+    /// The size of the slabs of each section
+    const SIZES: [usize; N];
+    /// The number of slots of each section, one of 1, 8, 16, 32, 64 or, with the `u128` feature,
+    /// 128
+    const SLOTS: [u32; N];
+    /// The total number of slots across all sections
+    const TOTAL_SLOTS: usize = total_slots(&Self::SLOTS);
+    /// The number of bytes of buffer needed to hold every section, wherever the buffer starts
+    const TOTAL_BYTES: usize = total_bytes(&Self::SIZES, &Self::SLOTS);
+
+    /// The sections described by the configuration
+    fn sections() -> [Section; N] {
+        core::array::from_fn(|i| {
+            Section::new(
+                Self::SIZES[i],
+                Atomics::with_slots(Self::SLOTS[i]).expect(INVALID_SLOT_COUNT),
+            )
+        })
+    }
+}
+
+/// The index of the first of `sizes` large enough for `layout`, for checking a configuration at
+/// compile time. This matches
+/// [`SlabAllocator::section_for_layout`](crate::SlabAllocator::section_for_layout) as long as the
+/// slabs are aligned for `layout`, which the buffer's alignment decides:
+/// ```
+/// # use slab_alloc::config::section_for_layout;
+/// # use core::alloc::Layout;
+/// const SIZES: [usize; 2] = [16, 128];
+/// const _: () = assert!(matches!(section_for_layout(&SIZES, Layout::new::<[u64; 4]>()), Some(1)));
+/// ```
+pub const fn section_for_layout(sizes: &[usize], layout: Layout) -> Option<usize> {
+    let size = layout.pad_to_align().size();
+    let mut i = 0;
+    while i < sizes.len() {
+        if sizes[i] >= size {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// A plain description of a section, usable in `const` items where a [`Section`] is not
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SectionSpec {
+    /// The size of the slabs
+    pub size: usize,
+    /// The number of slots, one of 1, 8, 16, 32, 64 or, with the `u128` feature, 128
+    pub slots: u32,
+    /// The alignment the section is placed on, as [`Section::with_align`] sets
+    pub align: usize,
+}
+
+impl SectionSpec {
+    /// A section of `slots` slabs of `size` bytes
+    pub const fn new(size: usize, slots: u32) -> Self {
+        Self {
+            size,
+            slots,
+            align: 1,
+        }
+    }
+
+    /// Places the section on multiples of `align` bytes, rounding `size` up to a multiple of it
+    pub const fn with_align(self, align: usize) -> Self {
+        Self {
+            size: self.size.next_multiple_of(align),
+            align,
+            ..self
+        }
+    }
+
+    /// The section described
+    pub const fn section(&self) -> Section {
+        Section::empty(self.size, self.slots).with_align(self.align)
+    }
+}
+
+/// The number of bytes of buffer that always fits `sections`, wherever the buffer starts, so a
+/// `static` byte array can be declared with it:
+/// ```
+/// # use slab_alloc::config::{required_buffer_size, SectionSpec};
+/// const SPEC: [SectionSpec; 2] = [SectionSpec::new(16, 64), SectionSpec::new(100, 8).with_align(4)];
+/// static mut BUF: [u8; required_buffer_size(&SPEC)] = [0; required_buffer_size(&SPEC)];
+/// # assert_eq!(required_buffer_size(&SPEC), 16 * 64 + 100 * 8 + 3);
+/// ```
+pub const fn required_buffer_size(sections: &[SectionSpec]) -> usize {
+    let mut len: usize = 0;
+    let mut largest = 1;
+    let mut i = 0;
+    while i < sections.len() {
+        let section = &sections[i];
+        len = len.next_multiple_of(section.align) + section.size * section.slots as usize;
+        if section.align > largest {
+            largest = section.align;
+        }
+        i += 1;
+    }
+    // A buffer that does not start on the largest alignment may need padding before it
+    len + largest - 1
+}
+
+/// Sections planned by [`plan`] for a set of objects, in increasing order of size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Plan<const N: usize> {
+    /// The size of the slabs of each section
+    pub sizes: [usize; N],
+    /// The alignment each section is placed on
+    pub aligns: [usize; N],
+    /// The number of slots of each section
+    pub slots: [u32; N],
+    /// The number of bytes of buffer the sections need, if it starts on the largest alignment
+    pub len: usize,
+}
+
+impl<const N: usize> Plan<N> {
+    /// The planned sections, for [`SlabAllocator::new_const`](crate::SlabAllocator::new_const)
+    /// or [`SlabAllocator::new`](crate::SlabAllocator::new)
+    pub const fn sections(&self) -> [Section; N] {
+        let mut sections = [const { Section::empty(1, 1) }; N];
+        let mut i = 0;
+        while i < N {
+            sections[i] = Section::empty(self.sizes[i], self.slots[i]).with_align(self.aligns[i]);
+            i += 1;
+        }
+        sections
+    }
+}
+
+/// Plans the sections for `objects` given as `(size, count)` pairs, in any order, usable in a
+/// `static` initializer. Each size is rounded up to the next power of two below the word size
+/// and to a multiple of the word size above it, so slabs are aligned for their objects, and each
+/// section gets the fewest slots covering its count.
+/// Panics if a size is zero or a count is more than a section can hold:
+/// ```
+/// # use slab_alloc::config::{plan, Plan};
+/// # use slab_alloc::{SlabAllocator, SlabStorage};
+/// const PLAN: Plan<2> = plan([(100, 3), (24, 10)]);
+/// static mut STORAGE: SlabStorage<{ PLAN.len }, 8> = SlabStorage::new();
+/// static ALLOC: SlabAllocator<'static, 2> = SlabAllocator::new_const(
+///     PLAN.sections(),
+///     unsafe { (*core::ptr::addr_of_mut!(STORAGE)).as_bytes_mut() },
+/// );
+/// # assert_eq!(PLAN.sizes, [24, 104]);
+/// # assert_eq!(PLAN.slots, [16, 8]);
+/// ```
+pub const fn plan<const N: usize>(objects: [(usize, usize); N]) -> Plan<N> {
+    const WORD: usize = mem::size_of::<usize>();
+    let mut sizes = [0; N];
+    let mut counts = [0; N];
+    let mut i = 0;
+    while i < N {
+        let (size, count) = objects[i];
+        assert!(size != 0, "Objects must have nonzero size");
+        // Insert in order of size
+        let mut j = i;
+        while j > 0 && sizes[j - 1] > size {
+            sizes[j] = sizes[j - 1];
+            counts[j] = counts[j - 1];
+            j -= 1;
+        }
+        sizes[j] = size;
+        counts[j] = count;
+        i += 1;
+    }
+
+    let mut plan = Plan {
+        sizes,
+        aligns: [1; N],
+        slots: [0; N],
+        len: 0,
+    };
+    let mut i = 0;
+    while i < N {
+        let align = if sizes[i] < WORD {
+            sizes[i].next_power_of_two()
+        } else {
+            WORD
+        };
+        plan.aligns[i] = align;
+        plan.sizes[i] = sizes[i].next_multiple_of(align);
+        plan.slots[i] = slots_for(counts[i]);
+        plan.len = plan.len.next_multiple_of(align) + plan.sizes[i] * plan.slots[i] as usize;
+        i += 1;
+    }
+    plan
+}
+
+const fn total_slots(slots: &[u32]) -> usize {
+    let mut total = 0;
+    let mut i = 0;
+    while i < slots.len() {
+        total += slots[i] as usize;
+        i += 1;
+    }
+    total
+}
+
+/// The buffer [`SlabConfig::sections`] needs, as [`required_buffer_size`] works it out
+const fn total_bytes<const N: usize>(sizes: &[usize; N], slots: &[u32; N]) -> usize {
+    let mut specs = [SectionSpec::new(0, 1); N];
+    let mut i = 0;
+    while i < N {
+        specs[i] = SectionSpec::new(sizes[i], slots[i]);
+        i += 1;
+    }
+    required_buffer_size(&specs)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+
+    struct Config;
+    impl SlabConfig<3> for Config {
+        const SIZES: [usize; 3] = [8, 32, 256];
+        const SLOTS: [u32; 3] = [64, 16, 1];
+    }
+
+    #[test]
+    fn consts() {
+        assert_eq!(Config::TOTAL_SLOTS, 81);
+        assert_eq!(Config::TOTAL_BYTES, 8 * 64 + 32 * 16 + 256);
+        assert_eq!(
+            Config::TOTAL_BYTES,
+            config::required_buffer_size(&[
+                config::SectionSpec::new(8, 64),
+                config::SectionSpec::new(32, 16),
+                config::SectionSpec::new(256, 1),
+            ])
+        );
+        assert_eq!(SlabAllocator::<3>::SECTIONS, 3);
+        const {
+            assert!(
+                config::section_for_layout(&Config::SIZES, Layout::new::<[u8; 300]>()).is_none()
+            )
+        };
+
+        let mut buf = [0u8; Config::TOTAL_BYTES];
+        let allocator = SlabAllocator::from_config::<Config>(&mut buf[..]).unwrap();
+        assert_eq!(allocator.blocks[1].size, 32);
+        assert_eq!(allocator.blocks[1].total_slots(), 16);
+        assert!(SlabAllocator::from_config::<Config>(&mut [0u8; 10][..]).is_err());
+    }
+
+    #[test]
+    fn required_buffer_size() {
+        const SPEC: [config::SectionSpec; 2] = [
+            config::SectionSpec::new(12, 8),
+            config::SectionSpec::new(20, 1).with_align(8),
+        ];
+        const LEN: usize = config::required_buffer_size(&SPEC);
+        assert_eq!(LEN, 96 + 24 + 7);
+
+        let mut buf = [0u8; LEN + 7];
+        for start in 0..8 {
+            let sections = [SPEC[0].section(), SPEC[1].section()];
+            assert!(SlabAllocator::new(sections, &mut buf[start..start + LEN]).is_ok());
+        }
+    }
+
+    #[test]
+    fn plan() {
+        const PLAN: config::Plan<3> = config::plan([(12, 40), (3, 1), (5, 9)]);
+        assert_eq!(PLAN.sizes, [4, 8, 16]);
+        assert_eq!(PLAN.aligns, [4, 8, 8]);
+        assert_eq!(PLAN.slots, [1, 16, 64]);
+        assert_eq!(PLAN.len, 8 + 8 * 16 + 16 * 64);
+
+        let mut buf = test::Aligned([0u8; PLAN.len]);
+        let allocator = SlabAllocator::new(PLAN.sections(), &mut buf.0[..]).unwrap();
+        assert_eq!(allocator.blocks[2].region.as_ptr() as usize % 8, 0);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::section::Section;
+use crate::{Event, SlabAllocator};
+use core::{fmt, ptr};
+
+/// Why a pointer could not be freed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeallocError {
+    /// The pointer is not within any slab of the allocator
+    Foreign,
+    /// The slab the pointer is within is not allocated, such as after a double free
+    NotAllocated,
+}
+
+impl fmt::Display for DeallocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Foreign => write!(f, "pointer was not allocated by this allocator"),
+            Self::NotAllocated => write!(f, "slab is not allocated"),
+        }
+    }
+}
+
+impl core::error::Error for DeallocError {}
+
+/// What [`Allocator::deallocate`](crate::Allocator::deallocate) does with a pointer it cannot
+/// free
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeallocPolicy {
+    /// Panic, the default
+    #[default]
+    Panic,
+    /// Do nothing
+    Ignore,
+    /// Report an [`Event::InvalidFree`] to the observer
+    Report,
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Sets what deallocating a pointer that cannot be freed does
+    pub fn set_dealloc_policy(&mut self, policy: DeallocPolicy) {
+        self.dealloc_policy = policy;
+    }
+
+    /// Frees the slab `ptr` points into, or reports why it cannot be freed rather than panicking,
+    /// for allocators layered over this one that may be given foreign pointers
+    ///
+    /// # Safety
+    /// If `ptr` is within a live allocation, the allocation must not be used afterwards
+    pub unsafe fn try_deallocate(&self, ptr: ptr::NonNull<u8>) -> Result<(), DeallocError> {
+        self.free_slot(self.locate(ptr)).map(|_| ())
+    }
+
+    /// Frees the slot a pointer was `located` in, returning the index of its section and the
+    /// slot
+    ///
+    /// # Safety
+    /// As for [`try_deallocate`](SlabAllocator::try_deallocate)
+    pub(crate) unsafe fn free_slot(
+        &self,
+        located: Option<(usize, &Section, u32)>,
+    ) -> Result<(usize, u32), DeallocError> {
+        let (index, section, slot) = located.ok_or(DeallocError::Foreign)?;
+        if !section.is_allocated(slot) {
+            return Err(DeallocError::NotAllocated);
+        }
+        let run = section.run_of(slot);
+        if run.count_ones() > 1 {
+            let mut slots = run;
+            while slots != 0 {
+                self.releasing(section, slots.trailing_zeros());
+                slots &= slots - 1;
+            }
+            section.deallocate_batch(run);
+        } else {
+            self.releasing(section, slot);
+            // A racing free may still get there first
+            section
+                .deallocate(slot)
+                .map_err(|_| DeallocError::NotAllocated)?;
+        }
+        self.released(section);
+        Ok((index, slot))
+    }
+
+    /// Handles a pointer that could not be freed according to the deallocation policy
+    pub(crate) fn invalid_free(&self, ptr: ptr::NonNull<u8>, error: DeallocError) {
+        match self.dealloc_policy {
+            DeallocPolicy::Panic => panic!("Could not deallocate {ptr:p}: {error}"),
+            DeallocPolicy::Ignore => {}
+            DeallocPolicy::Report => {
+                if let Some(observer) = self.observer {
+                    observer(&Event::InvalidFree(ptr, error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::ptr::NonNull;
+    use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+    static INVALID: AtomicUsize = AtomicUsize::new(0);
+
+    #[allow(irrefutable_let_patterns)]
+    fn observer(event: &Event<'_>) {
+        if let Event::InvalidFree(_, DeallocError::NotAllocated) = event {
+            INVALID.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn try_deallocate() {
+        let mut buf = Aligned([0u8; 64]);
+        let mut other = 0u8;
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap().cast::<u8>();
+
+        unsafe {
+            assert_eq!(
+                allocator.try_deallocate(NonNull::from(&mut other)),
+                Err(DeallocError::Foreign)
+            );
+            assert_eq!(allocator.try_deallocate(slab), Ok(()));
+            assert_eq!(
+                allocator.try_deallocate(slab),
+                Err(DeallocError::NotAllocated)
+            );
+        }
+
+        allocator.set_observer(observer);
+        allocator.set_dealloc_policy(DeallocPolicy::Report);
+        unsafe { allocator.deallocate(slab, layout) };
+        assert_eq!(INVALID.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "slab is not allocated")]
+    fn double_free() {
+        let mut buf = Aligned([0u8; 64]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap().cast::<u8>();
+        unsafe {
+            allocator.deallocate(slab, layout);
+            allocator.deallocate(slab, layout);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::api::{AllocError, Allocator};
+use crate::section::Section;
+use crate::{check_order, find_section, place, sanitize, CreateError};
+use core::{alloc, marker, ptr};
+
+/// Slab allocator whose sections are a runtime sized slice rather than an array, for systems
+/// that only learn their size classes at runtime. Sections are used in the order given, so they
+/// must be sorted by size
+pub struct DynSlabAllocator<'m> {
+    blocks: &'m [Section],
+    memory: ptr::NonNull<[u8]>,
+    _buffer: marker::PhantomData<&'m mut [u8]>,
+}
+
+// Sections are only modified atomically, and the buffer only through slots claimed in them
+unsafe impl<'m> Sync for DynSlabAllocator<'m> {}
+// The allocator holds the only borrow of its buffer
+unsafe impl<'m> Send for DynSlabAllocator<'m> {}
+
+impl<'m> DynSlabAllocator<'m> {
+    /// Constructor for [`DynSlabAllocator`] where
+    /// `blocks` are the sections of the allocator and
+    /// `buf` is the memory buffer that the allocator will allocate from.
+    /// Fails as [`SlabAllocator::new`] does
+    pub fn new(blocks: &'m mut [Section], buf: &'m mut [u8]) -> Result<Self, CreateError> {
+        check_order(blocks)?;
+        let memory = ptr::NonNull::from(buf);
+        place(blocks, memory)?;
+        sanitize::created(memory);
+        Ok(Self {
+            blocks,
+            memory,
+            _buffer: marker::PhantomData,
+        })
+    }
+
+    /// The sections of the allocator
+    pub fn sections(&self) -> &[Section] {
+        self.blocks
+    }
+
+    /// Whether `ptr` points into a slab of this allocator, allocated or not
+    pub fn owns(&self, ptr: ptr::NonNull<u8>) -> bool {
+        self.locate(ptr).is_some()
+    }
+
+    /// The section `ptr` was allocated from and the slot within it
+    fn locate(&self, ptr: ptr::NonNull<u8>) -> Option<(&Section, u32)> {
+        let (index, slot) = find_section(self.blocks, ptr)?;
+        Some((&self.blocks[index], slot))
+    }
+}
+
+#[cfg(feature = "sanitize")]
+impl<'m> Drop for DynSlabAllocator<'m> {
+    fn drop(&mut self) {
+        sanitize::destroyed(self.memory);
+    }
+}
+
+unsafe impl<'m> Allocator for DynSlabAllocator<'m> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Find the smallest size section large enough for the layout
+        let section = self
+            .blocks
+            .iter()
+            .find(|section| section.fits(layout) && section.free_slots() > 0)
+            .ok_or(AllocError)?;
+        let slot = section.allocate()?;
+
+        let slab = section.slot_ptr(slot);
+        sanitize::allocated(self.memory, slab);
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, _layout: alloc::Layout) {
+        let (section, slot) = self
+            .locate(ptr)
+            .expect("Could not deallocate slab: could not find section ptr is allocated in");
+
+        let slab = section.slot_ptr(slot);
+        section.clear_freed(slab);
+        sanitize::freed(self.memory, slab);
+        section
+            .deallocate(slot)
+            .expect("Could not deallocate block");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU8};
+
+    #[test]
+    fn runtime_sections() {
+        let mut buf = [0u8; 4 * 16 + 16 * 8 + 64];
+        let mut sections = [
+            Section::new(4, AtomicU16::new(0)),
+            Section::new(16, AtomicU8::new(0)),
+            Section::new(64, AtomicBool::new(false)),
+        ];
+        {
+            let count = 2;
+            let allocator = DynSlabAllocator::new(&mut sections[..count], &mut buf[..]).unwrap();
+            assert_eq!(allocator.sections().len(), 2);
+
+            let layout = Layout::new::<[u8; 10]>();
+            let slab = allocator.allocate(layout).unwrap();
+            assert_eq!(slab.len(), 16);
+            assert!(allocator.allocate(Layout::new::<[u8; 17]>()).is_err());
+            unsafe { allocator.deallocate(slab.cast(), layout) };
+            assert_eq!(allocator.sections()[1].free_slots(), 8);
+        }
+        assert!(DynSlabAllocator::new(&mut sections[..], &mut [0u8; 16][..]).is_err());
+    }
+
+    #[test]
+    fn many_sections() {
+        let mut buf = [0u8; 8 * (1 + 16) * 16 / 2];
+        let mut sections: [_; 16] =
+            core::array::from_fn(|index| Section::new(8 * (index + 1), AtomicBool::new(false)));
+        let allocator = DynSlabAllocator::new(&mut sections[..], &mut buf[..]).unwrap();
+
+        let layouts: [_; 16] =
+            core::array::from_fn(|index| Layout::from_size_align(8 * (index + 1), 1).unwrap());
+        let slabs = layouts.map(|layout| allocator.allocate(layout).unwrap());
+        for (slab, layout) in slabs.into_iter().zip(layouts).rev() {
+            assert!(allocator.owns(slab.cast()));
+            unsafe { allocator.deallocate(slab.cast(), layout) };
+        }
+        assert!(allocator
+            .sections()
+            .iter()
+            .all(|section| section.free_slots() == 1));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::api::AllocError;
+use crate::{dangling, SlabAllocator};
+use core::{alloc, fmt, ptr};
+
+/// Why an allocation or free made through [`try_allocate`](SlabAllocator::try_allocate) or
+/// [`try_free`](SlabAllocator::try_free) failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SlabError {
+    /// No section has slabs large or aligned enough for the layout
+    NoSectionFits {
+        /// The size of the layout
+        size: usize,
+        /// The alignment of the layout
+        align: usize,
+    },
+    /// The first section able to hold the layout is full, as is every section it could spill into
+    SectionFull {
+        /// The index of the section
+        index: usize,
+    },
+    /// The pointer is within a slab but not at its start
+    Misaligned,
+    /// The pointer is not within any slab of the allocator
+    ForeignPointer,
+    /// The slab is not allocated, such as after a double free
+    DoubleFree {
+        /// The index of the section of the slab
+        section: usize,
+        /// The slot of the slab within its section
+        slot: u32,
+    },
+}
+
+impl fmt::Display for SlabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSectionFits { size, align } => {
+                write!(f, "no section fits {size} bytes aligned to {align}")
+            }
+            Self::SectionFull { index } => write!(f, "section {index} is full"),
+            Self::Misaligned => write!(f, "pointer is not at the start of a slab"),
+            Self::ForeignPointer => write!(f, "pointer was not allocated by this allocator"),
+            Self::DoubleFree { section, slot } => {
+                write!(f, "slot {slot} of section {section} is not allocated")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SlabError {}
+
+impl From<SlabError> for AllocError {
+    fn from(_: SlabError) -> Self {
+        AllocError
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Allocates a slab like [`Allocator::allocate`](crate::Allocator::allocate), saying why if
+    /// it cannot
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn try_allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, SlabError> {
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        match self.take_slab(layout) {
+            Ok(slab) => {
+                self.served(layout, slab);
+                Ok(slab)
+            }
+            Err(_) => {
+                self.failed(layout);
+                Err(match self.section_for_layout(layout) {
+                    Some(index) => SlabError::SectionFull { index },
+                    None => SlabError::NoSectionFits {
+                        size: layout.size(),
+                        align: layout.align(),
+                    },
+                })
+            }
+        }
+    }
+
+    /// Claims a slab for `layout` from the sections able to hold it, a run of slots in a
+    /// spanning section or a newly grown section, without reporting the outcome
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub(crate) fn take_slab(
+        &self,
+        layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.find_slab(layout, |_| true)
+            .or_else(|_| self.find_run(layout))
+            .or_else(|_| self.grow_slab(layout))
+    }
+
+    /// Frees the slab at `ptr` like [`Allocator::deallocate`](crate::Allocator::deallocate),
+    /// but returns why it cannot be freed rather than applying the
+    /// [`DeallocPolicy`](crate::DeallocPolicy)
+    ///
+    /// # Safety
+    /// If `ptr` is the start of an allocated slab, it must have been allocated with `layout` and
+    /// not be used afterwards
+    pub unsafe fn try_free(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        layout: alloc::Layout,
+    ) -> Result<(), SlabError> {
+        if layout.size() == 0 {
+            return Ok(());
+        }
+        let (index, section, slot) = self
+            .locate_for(ptr, layout)
+            .ok_or(SlabError::ForeignPointer)?;
+        if section.slot_ptr(slot).cast() != ptr {
+            return Err(SlabError::Misaligned);
+        }
+        if !section.is_allocated(slot) {
+            return Err(SlabError::DoubleFree {
+                section: index,
+                slot,
+            });
+        }
+        self.free_slot(Some((index, section, slot)))
+            .expect("Slab is allocated");
+        self.hook_deallocated(layout, index, slot);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::ptr::NonNull;
+
+    #[test]
+    fn diagnose() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        assert_eq!(
+            allocator.try_allocate(Layout::new::<[u8; 65]>()),
+            Err(SlabError::NoSectionFits { size: 65, align: 1 })
+        );
+        let layout = Layout::new::<[u8; 64]>();
+        let slab = allocator.try_allocate(layout).unwrap().cast::<u8>();
+        assert_eq!(
+            allocator.try_allocate(layout),
+            Err(SlabError::SectionFull { index: 1 })
+        );
+
+        let mut foreign = 0u8;
+        unsafe {
+            assert_eq!(
+                allocator.try_free(NonNull::from(&mut foreign), layout),
+                Err(SlabError::ForeignPointer)
+            );
+            assert_eq!(
+                allocator.try_free(slab.add(1), layout),
+                Err(SlabError::Misaligned)
+            );
+            assert_eq!(allocator.try_free(slab, layout), Ok(()));
+            assert_eq!(
+                allocator.try_free(slab, layout),
+                Err(SlabError::DoubleFree {
+                    section: 1,
+                    slot: 0
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::section::Section;
+use core::{alloc, fmt};
+
+/// Function called with the notable events of an allocator
+pub type Observer = fn(&Event<'_>);
+
+/// A notable event in an allocator, reported to its [`Observer`]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// An allocation failed, with the state of the allocator at the time
+    OutOfMemory(OomDump<'a>),
+    /// A [`ScopedStats`](crate::stats::ScopedStats) guard was dropped, with the activity during
+    /// its lifetime
+    #[cfg(feature = "stats")]
+    ScopeEnded(crate::stats::Stats),
+    /// A pointer could not be freed, reported under [`DeallocPolicy::Report`](crate::DeallocPolicy)
+    InvalidFree(core::ptr::NonNull<u8>, crate::DeallocError),
+    /// An allocation left the section at the index with fewer free slots, given, than its
+    /// [watermark](Section::with_watermark)
+    LowMemory(usize, u32),
+    /// A slab was still allocated when the allocator was checked for leaks
+    #[cfg(feature = "leak-check")]
+    Leak(crate::leak::Leak),
+}
+
+/// Compact description of the allocator when an allocation failed.
+/// Displays as the requested layout followed by `size:used/total` for each section
+pub struct OomDump<'a> {
+    /// The layout that could not be allocated
+    pub layout: alloc::Layout,
+    pub(crate) sections: &'a [Section],
+}
+
+impl<'a> OomDump<'a> {
+    /// The sections of the allocator
+    pub fn sections(&self) -> &'a [Section] {
+        self.sections
+    }
+}
+
+impl fmt::Display for OomDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OOM {}/{}:{}",
+            self.layout.size(),
+            self.layout.align(),
+            Occupancy(self.sections)
+        )
+    }
+}
+
+/// Displays as ` size:used/total` for each section
+pub(crate) struct Occupancy<'a>(pub(crate) &'a [Section]);
+
+impl fmt::Display for Occupancy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in self.0 {
+            write!(
+                f,
+                " {}:{}/{}",
+                section.size,
+                section.total_slots() - section.free_slots(),
+                section.total_slots()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "oom-dump"))]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+    static DUMPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[allow(irrefutable_let_patterns)]
+    fn observer(event: &Event<'_>) {
+        extern crate std;
+        use std::string::ToString;
+        if let Event::OutOfMemory(dump) = event {
+            assert_eq!(dump.to_string(), "OOM 4/1: 4:8/8");
+            DUMPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn oom_dump() {
+        let mut buf = [0u8; 32];
+        let mut allocator =
+            SlabAllocator::new([Section::new(4, AtomicU8::new(0))], &mut buf[..]).unwrap();
+        allocator.set_observer(observer);
+
+        let layout = Layout::new::<[u8; 4]>();
+        for _ in 0..8 {
+            allocator.allocate(layout).unwrap();
+        }
+        assert!(allocator.allocate(layout).is_err());
+        assert!(allocator.allocate(layout).is_err());
+        assert_eq!(DUMPS.load(Ordering::Relaxed), 1);
+
+        allocator.rearm_oom_dump();
+        assert!(allocator.allocate(layout).is_err());
+        assert_eq!(DUMPS.load(Ordering::Relaxed), 2);
+    }
+}
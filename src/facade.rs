@@ -0,0 +1,156 @@
+use crate::atomic::{AtomicUsize, Ordering};
+use crate::section::Section;
+use crate::SlabAllocator;
+use std::boxed::Box;
+use std::string::ToString;
+use std::vec::Vec;
+
+/// Handles of the metrics describing an allocator
+pub(crate) struct Metrics {
+    used: Vec<metrics::Gauge>,
+    failures: metrics::Counter,
+    high_water: metrics::Gauge,
+    peak: AtomicUsize,
+}
+
+impl Metrics {
+    /// Records the occupancy of `section` after one of its slots was allocated or freed
+    pub(crate) fn update(&self, blocks: &[Section], section: &Section) {
+        if let Some(index) = blocks
+            .iter()
+            .position(|other| core::ptr::eq(other, section))
+        {
+            self.used[index].set(section.total_slots() - section.free_slots());
+        }
+        let bytes = blocks
+            .iter()
+            .map(|section| (section.total_slots() - section.free_slots()) as usize * section.size)
+            .sum::<usize>();
+        if self.peak.fetch_max(bytes, Ordering::Relaxed) < bytes {
+            self.high_water.set(bytes as f64);
+        }
+    }
+
+    /// Records an allocation that could not be satisfied
+    pub(crate) fn failed(&self) {
+        self.failures.increment(1);
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Registers the allocator's metrics with the `metrics` recorder, which must already be
+    /// installed:
+    /// - `slab_alloc_used_slots` and `slab_alloc_total_slots` gauges, labelled with the
+    ///   `section` index and slab `size`
+    /// - a `slab_alloc_failures` counter
+    /// - a `slab_alloc_high_water_bytes` gauge of the most bytes of slabs allocated at once
+    pub fn install_metrics(&mut self) {
+        let used = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(index, section)| {
+                let labels = [
+                    ("section", index.to_string()),
+                    ("size", section.size.to_string()),
+                ];
+                metrics::gauge!("slab_alloc_total_slots", &labels).set(section.total_slots());
+                let used = metrics::gauge!("slab_alloc_used_slots", &labels);
+                used.set(section.total_slots() - section.free_slots());
+                used
+            })
+            .collect();
+        self.metrics = Some(Box::new(Metrics {
+            used,
+            failures: metrics::counter!("slab_alloc_failures"),
+            high_water: metrics::gauge!("slab_alloc_high_water_bytes"),
+            peak: AtomicUsize::new(0),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+    use std::string::{String, ToString};
+    use std::sync::{Arc, Mutex};
+    use std::vec::Vec;
+
+    /// Recorder keeping the value of every metric registered
+    #[derive(Default)]
+    struct Values(Mutex<Vec<(String, Arc<AtomicU64>)>>);
+
+    impl Values {
+        fn register(&self, key: &Key) -> Arc<AtomicU64> {
+            let mut name = key.name().to_string();
+            for label in key.labels() {
+                name += &std::format!(",{}={}", label.key(), label.value());
+            }
+            let value = Arc::new(AtomicU64::new(0));
+            self.0.lock().unwrap().push((name, value.clone()));
+            value
+        }
+
+        fn get(&self, name: &str) -> f64 {
+            let values = self.0.lock().unwrap();
+            let (_, value) = values.iter().find(|(key, _)| key == name).unwrap();
+            f64::from_bits(value.load(Ordering::Relaxed))
+        }
+    }
+
+    impl Recorder for Values {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            // Counters are read back as integers
+            Counter::from_arc(self.register(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.register(key))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn gauges() {
+        let mut buf = [0u8; 64];
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicU8::new(0)),
+                Section::new(16, AtomicBool::new(false)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+        let values = Values::default();
+        metrics::with_local_recorder(&values, || allocator.install_metrics());
+        assert_eq!(values.get("slab_alloc_total_slots,section=0,size=4"), 8.0);
+
+        let layout = Layout::new::<[u8; 16]>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert!(allocator.allocate(layout).is_err());
+        assert_eq!(values.get("slab_alloc_used_slots,section=1,size=16"), 1.0);
+        assert_eq!(values.get("slab_alloc_high_water_bytes"), 16.0);
+
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+        assert_eq!(values.get("slab_alloc_used_slots,section=1,size=16"), 0.0);
+        assert_eq!(values.get("slab_alloc_high_water_bytes"), 16.0);
+        let failures = values.0.lock().unwrap();
+        let (_, failures) = failures
+            .iter()
+            .find(|(key, _)| key == "slab_alloc_failures")
+            .unwrap();
+        assert_eq!(failures.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::section::Section;
+use crate::SlabAllocator;
+use core::alloc;
+use core::sync::atomic::Ordering;
+
+/// How the section an allocation is served from is chosen among those able to hold it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FitPolicy {
+    /// The first section in order with a free slot, the default. Since sections are in
+    /// increasing order of size this is the smallest of the allocator's own sections, and the
+    /// scan stops as soon as one is found
+    #[default]
+    FirstFit,
+    /// The section with the smallest slabs among every section with a free slot, including
+    /// those added since the allocator was created, preferring the fullest of equal sized ones
+    /// so the others stay empty. Scans every section and ignores interleaving
+    BestFit,
+    /// Only sections whose slabs are exactly the size of the allocation, failing otherwise, so no
+    /// slab is wasted on a smaller allocation
+    ExactFit,
+}
+
+impl FitPolicy {
+    /// Whether the policy lets `section` hold an allocation of `layout`
+    pub(crate) fn accepts(self, section: &Section, layout: alloc::Layout) -> bool {
+        section.fits(layout)
+            && (self != FitPolicy::ExactFit || section.size == layout.pad_to_align().size())
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Sets how the section an allocation is served from is chosen
+    pub fn set_fit_policy(&mut self, policy: FitPolicy) {
+        self.fit_policy = policy;
+    }
+
+    /// The section with a free slot an allocation of `layout` is served from, among those whose
+    /// index is `allowed`, according to the fit policy
+    pub(crate) fn choose_section(
+        &self,
+        layout: alloc::Layout,
+        allowed: impl Fn(usize) -> bool,
+    ) -> Option<&Section> {
+        let policy = self.fit_policy;
+        let candidate =
+            |section: &Section| policy.accepts(section, layout) && section.free_slots() > 0;
+        if policy == FitPolicy::BestFit {
+            return self
+                .indexed()
+                .filter(|(index, section)| allowed(*index) && candidate(section))
+                .min_by_key(|(_, section)| (section.size, section.free_slots()))
+                .map(|(_, section)| section);
+        }
+
+        // Find the smallest size section large enough for the layout
+        let Some((_, section)) = self
+            .blocks
+            .iter()
+            .enumerate()
+            .find(|(index, section)| allowed(*index) && candidate(section))
+        else {
+            // Then any section added since, grown or given with `add_region`
+            return self
+                .extra()
+                .filter(|section| allowed(section.index) && candidate(section))
+                .min_by_key(|section| section.size);
+        };
+        if !self.interleave {
+            return Some(section);
+        }
+
+        // Rotate between the sections with the same slab size
+        let banks = self.blocks.iter().enumerate().filter(|(index, other)| {
+            allowed(*index) && other.size == section.size && policy.accepts(other, layout)
+        });
+        let skip = self.next_bank.fetch_add(1, Ordering::Relaxed) % banks.clone().count();
+        banks
+            .clone()
+            .skip(skip)
+            .chain(banks.take(skip))
+            .map(|(_, other)| other)
+            .find(|other| other.free_slots() > 0)
+            .or(Some(section))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn fit_policy() {
+        let mut buf = Aligned([0u8; 8 * 8 + 2 * 16 * 8 + 32]);
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(16, AtomicU8::new(0)),
+                Section::new(16, AtomicU8::new(0)),
+                Section::new(32, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let section = |allocator: &SlabAllocator<4>, slab: core::ptr::NonNull<[u8]>| {
+            allocator.layout_of(slab.cast()).unwrap().section
+        };
+
+        allocator.set_fit_policy(FitPolicy::ExactFit);
+        assert!(allocator.allocate(Layout::new::<[u8; 20]>()).is_err());
+        let slab = allocator.allocate(Layout::new::<[u8; 16]>()).unwrap();
+        assert_eq!(section(&allocator, slab), 1);
+        unsafe { allocator.deallocate(slab.cast(), Layout::new::<[u8; 16]>()) };
+
+        // Best fit packs equal sized sections, leaving the emptier ones alone
+        allocator.set_fit_policy(FitPolicy::BestFit);
+        let layout = Layout::new::<[u8; 12]>();
+        let pinned = allocator.allocate_at(2, 0).unwrap();
+        let slab = allocator.allocate(layout).unwrap();
+        assert_eq!(section(&allocator, slab), 2);
+        unsafe {
+            allocator.deallocate(slab.cast(), layout);
+            allocator.deallocate(pinned.cast(), layout);
+        }
+
+        allocator.set_fit_policy(FitPolicy::FirstFit);
+        let slab = allocator.allocate(Layout::new::<[u8; 20]>()).unwrap();
+        assert_eq!(section(&allocator, slab), 3);
+        unsafe { allocator.deallocate(slab.cast(), Layout::new::<[u8; 20]>()) };
+    }
+}
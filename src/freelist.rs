@@ -0,0 +1,197 @@
+use crate::atomic::{AtomicU32, AtomicU64, Ordering};
+use crate::sanitize;
+use crate::section::Section;
+use core::{mem, ptr};
+
+/// The link of the last free slot
+const NIL: u32 = u32::MAX;
+
+/// The part of the head counting the changes made to it
+const TAG: u64 = !(NIL as u64);
+
+/// One change to the head
+const STEP: u64 = 1 << u32::BITS;
+
+/// The free slots of a section chosen with [`Section::with_free_list`], a Treiber stack of slot
+/// indices threaded through the free slabs themselves, so finding a free slot pops the list
+/// rather than scanning the bitmap. The bitmap still records which slots are allocated
+pub(crate) struct FreeList {
+    /// The first free slot in the low half, or [`NIL`], and in the high half a count of the
+    /// changes made, so a pop that raced with others cannot install a stale link
+    head: AtomicU64,
+}
+
+impl FreeList {
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: AtomicU64::new(NIL as u64),
+        }
+    }
+
+    /// The link to the next free slot kept at the start of slot `index` of `section`
+    fn link(section: &Section, index: u32) -> ptr::NonNull<AtomicU32> {
+        section.slot_ptr(index).cast()
+    }
+
+    /// Threads the list through every free slot of `section`, lowest first
+    ///
+    /// # Safety
+    /// The section must be placed, its free slabs unused and no allocation or free may run at
+    /// the same time
+    pub(crate) unsafe fn relink(&self, section: &Section) {
+        assert!(
+            section.slot_align() >= mem::align_of::<AtomicU32>(),
+            "Free list slabs must be aligned for their links"
+        );
+        let free = !section.bitmap() & section.full();
+        let mut next = NIL;
+        for index in (0..section.total_slots()).rev() {
+            if free & 1 << index != 0 {
+                Self::write_link(section, index, next);
+                next = index;
+            }
+        }
+        let head = self.head.load(Ordering::Relaxed);
+        self.head.store(
+            (head & TAG).wrapping_add(STEP) | next as u64,
+            Ordering::Release,
+        );
+    }
+
+    /// Writes `next` as the link of the free slot `index`
+    ///
+    /// # Safety
+    /// The slot must be free and its memory unused
+    unsafe fn write_link(section: &Section, index: u32, next: u32) {
+        let link = Self::link(section, index);
+        // Free slabs are poisoned for the sanitizers, all but the links the list keeps in them
+        sanitize::unpoison(ptr::NonNull::slice_from_raw_parts(
+            link.cast(),
+            mem::size_of::<AtomicU32>(),
+        ));
+        link.as_ref().store(next, Ordering::Relaxed);
+    }
+
+    /// Pops the first free slot off the list, retrying as long as other threads change it
+    /// first if `retry` is set
+    pub(crate) fn pop(&self, section: &Section, retry: bool) -> Option<u32> {
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            let index = head as u32;
+            if index == NIL {
+                return None;
+            }
+            // The slot may be popped and written by another thread meanwhile, in which case the
+            // changed tag makes the exchange fail and the stale link is never installed
+            // SAFETY: the link lies within the section and is only accessed atomically by the list
+            let next = unsafe { Self::link(section, index).as_ref() }.load(Ordering::Relaxed);
+            let new = (head & TAG).wrapping_add(STEP) | next as u64;
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => return Some(index),
+                Err(_) if !retry => return None,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Pushes the free slot `index` onto the list
+    ///
+    /// # Safety
+    /// The slot must have just been freed and its memory no longer used
+    pub(crate) unsafe fn push(&self, section: &Section, index: u32) {
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            Self::write_link(section, index, head as u32);
+            let new = (head & TAG).wrapping_add(STEP) | index as u64;
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicU64, AtomicU8};
+
+    #[test]
+    fn free_list() {
+        let mut buf = Aligned([0u8; 8 * 8 + 64 * 8]);
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0b10)).with_free_list(),
+                Section::new(8, AtomicU64::new(0)).with_free_list(),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let layout = Layout::new::<u64>();
+
+        // Slots already allocated are left out of the list
+        let slabs: [_; 71] = core::array::from_fn(|_| allocator.allocate(layout).unwrap());
+        assert_eq!(allocator.blocks[0].slot_of(slabs[1].cast()), Some(2));
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [8, 64]);
+        assert!(allocator.allocate(layout).is_err());
+        for slab in slabs {
+            unsafe { slab.cast::<u64>().write(u64::MAX) };
+        }
+
+        // The slots freed last are handed out first
+        unsafe {
+            allocator.deallocate(slabs[40].cast(), layout);
+            allocator.deallocate(slabs[20].cast(), layout);
+        }
+        assert_eq!(allocator.gauges()[1].get(), 62);
+        assert_eq!(allocator.allocate(layout).unwrap(), slabs[20]);
+        assert_eq!(allocator.allocate(layout).unwrap(), slabs[40]);
+        // Specific slots cannot be taken from the list
+        unsafe { allocator.deallocate(slabs[3].cast(), layout) };
+        assert!(allocator.allocate_at(0, 4).is_err());
+        assert_eq!(allocator.verify(), Ok(()));
+    }
+
+    #[test]
+    fn concurrent() {
+        extern crate std;
+        use core::sync::atomic::Ordering;
+        let mut buf = Aligned([0u8; 64 * 8]);
+        let allocator = SlabAllocator::new(
+            [Section::new(8, AtomicU64::new(0)).with_free_list()],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let layout = Layout::new::<u64>();
+        let held = AtomicU64::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..8 {
+                        let slabs: [_; 8] =
+                            core::array::from_fn(|_| allocator.allocate(layout).unwrap());
+                        for slab in slabs {
+                            // No slot is handed out twice
+                            let bit = 1 << allocator.blocks[0].slot_of(slab.cast()).unwrap();
+                            assert_eq!(held.fetch_or(bit, Ordering::Relaxed) & bit, 0);
+                        }
+                        for slab in slabs {
+                            let bit = 1 << allocator.blocks[0].slot_of(slab.cast()).unwrap();
+                            held.fetch_and(!bit, Ordering::Relaxed);
+                            unsafe { allocator.deallocate(slab.cast(), layout) };
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(allocator.gauges()[0].get(), 0);
+    }
+}
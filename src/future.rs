@@ -0,0 +1,270 @@
+use crate::error::SlabError;
+use crate::section::Section;
+use crate::{dangling, SlabAllocator};
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::{alloc, marker, mem, ptr};
+
+/// The number of futures that can wait on one section at a time
+pub(crate) const WAITERS: usize = 4;
+
+/// A place for one waiting future to leave its waker, woken when a slot of the section is freed
+pub(crate) struct Waiter {
+    /// The address of the future that has taken this place, or null
+    owner: AtomicPtr<()>,
+    /// Whether the waker is being replaced or taken
+    locked: AtomicBool,
+    /// The waker left, if any. Not an `Option<Waker>` so sections stay free of drop glue and
+    /// can be built in const functions: a waiting future always takes its waker back when
+    /// dropped
+    waker: UnsafeCell<Option<mem::ManuallyDrop<Waker>>>,
+}
+
+// The waker is only reached while holding the lock
+unsafe impl Sync for Waiter {}
+
+impl Waiter {
+    pub(crate) const fn new() -> Self {
+        Self {
+            owner: AtomicPtr::new(ptr::null_mut()),
+            locked: AtomicBool::new(false),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Whether the future at `owner` holds this place, taking it if it is free
+    fn take(&self, owner: *mut ()) -> bool {
+        match self.owner.compare_exchange(
+            ptr::null_mut(),
+            owner,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => true,
+            Err(current) => current == owner,
+        }
+    }
+
+    /// Leaves `waker` to be woken, replacing any left before
+    fn register(&self, waker: &Waker) {
+        // Only a wake, which never waits for the lock, can hold it
+        while self.locked.swap(true, Ordering::Acquire) {
+            core::hint::spin_loop();
+        }
+        // SAFETY: the lock is held
+        let slot = unsafe { &mut *self.waker.get() };
+        if !slot.as_ref().is_some_and(|left| left.will_wake(waker)) {
+            if let Some(left) = slot.replace(mem::ManuallyDrop::new(waker.clone())) {
+                drop(mem::ManuallyDrop::into_inner(left));
+            }
+        }
+        self.locked.store(false, Ordering::Release);
+    }
+
+    /// Wakes the waker left, if any. Skipped if a waker is being registered, as the future
+    /// registering it tries to allocate again afterwards
+    fn wake(&self) {
+        if self.locked.swap(true, Ordering::Acquire) {
+            return;
+        }
+        // SAFETY: the lock is held
+        let waker = unsafe { (*self.waker.get()).take() };
+        self.locked.store(false, Ordering::Release);
+        if let Some(waker) = waker {
+            mem::ManuallyDrop::into_inner(waker).wake();
+        }
+    }
+
+    /// Gives the place up, dropping any waker left
+    fn release(&self) {
+        while self.locked.swap(true, Ordering::Acquire) {
+            core::hint::spin_loop();
+        }
+        // SAFETY: the lock is held
+        let waker = unsafe { (*self.waker.get()).take() };
+        self.locked.store(false, Ordering::Release);
+        self.owner.store(ptr::null_mut(), Ordering::Release);
+        drop(waker.map(mem::ManuallyDrop::into_inner));
+    }
+}
+
+/// Wakes the futures waiting on `section`, one of whose slots has just been freed
+pub(crate) fn wake(section: &Section) {
+    for waiter in &section.waiters {
+        if !waiter.owner.load(Ordering::Acquire).is_null() {
+            waiter.wake();
+        }
+    }
+}
+
+/// Future returned by [`allocate_async`](SlabAllocator::allocate_async), resolving to a slab once
+/// one is free. It leaves its waker with every section able to hold the layout, found by its
+/// address, so it cannot be moved once polled
+#[must_use = "futures do nothing unless polled"]
+pub struct Allocation<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    layout: alloc::Layout,
+    /// Whether the future has taken a place in any section
+    waiting: bool,
+    _pinned: marker::PhantomPinned,
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Allocates a slab for `layout`, waiting until a slot of a section able to hold it is freed
+    /// if every one is full, rather than failing. Fails at once with
+    /// [`SlabError::NoSectionFits`] if no section can ever hold it. If more futures than a
+    /// section has places for wait on it, the rest are polled again at once instead of waiting
+    pub fn allocate_async(&self, layout: alloc::Layout) -> Allocation<'_, 'm, N> {
+        Allocation {
+            allocator: self,
+            layout,
+            waiting: false,
+            _pinned: marker::PhantomPinned,
+        }
+    }
+}
+
+impl<'a, const N: usize> Allocation<'a, '_, N> {
+    /// The sections able to hold the layout, including those added since the allocator was
+    /// created
+    fn sections(&self) -> impl Iterator<Item = &'a Section> {
+        let (allocator, layout) = (self.allocator, self.layout);
+        allocator
+            .blocks
+            .iter()
+            .chain(allocator.extra())
+            .filter(move |section| allocator.fit_policy.accepts(section, layout))
+    }
+
+    /// The address the future's places are taken under
+    fn id(&self) -> *mut () {
+        ptr::from_ref(self).cast_mut().cast()
+    }
+}
+
+impl<const N: usize> Future for Allocation<'_, '_, N> {
+    type Output = Result<ptr::NonNull<[u8]>, SlabError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let (allocator, layout) = (self.allocator, self.layout);
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            return Poll::Ready(Ok(dangling(layout)));
+        }
+        if self.sections().next().is_none() {
+            return Poll::Ready(allocator.try_allocate(layout));
+        }
+        if let Ok(slab) = allocator.take_slab(layout) {
+            allocator.served(layout, slab);
+            return Poll::Ready(Ok(slab));
+        }
+
+        let id = self.id();
+        let mut left_out = false;
+        for section in self.sections() {
+            match section.waiters.iter().find(|waiter| waiter.take(id)) {
+                Some(waiter) => waiter.register(cx.waker()),
+                None => left_out = true,
+            }
+        }
+        // SAFETY: only a flag is changed, the future is not moved
+        unsafe { self.get_unchecked_mut().waiting = true };
+        if left_out {
+            cx.waker().wake_by_ref();
+        }
+        // A slot freed before the waker was left would not have woken it
+        match allocator.take_slab(layout) {
+            Ok(slab) => {
+                allocator.served(layout, slab);
+                Poll::Ready(Ok(slab))
+            }
+            Err(_) => Poll::Pending,
+        }
+    }
+}
+
+impl<const N: usize> Drop for Allocation<'_, '_, N> {
+    fn drop(&mut self) {
+        if !self.waiting {
+            return;
+        }
+        let id = self.id();
+        // Sections cannot be removed while the allocator is borrowed, so every place taken is
+        // still reachable
+        for section in self.allocator.blocks.iter().chain(self.allocator.extra()) {
+            for waiter in &section.waiters {
+                if waiter.owner.load(Ordering::Acquire) == id {
+                    waiter.release();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::ptr;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+    fn waker() -> Waker {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(ptr::null(), &VTABLE),
+            |_| {
+                WOKEN.fetch_add(1, Ordering::Relaxed);
+            },
+            |_| {
+                WOKEN.fetch_add(1, Ordering::Relaxed);
+            },
+            |_| {},
+        );
+        unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn allocate_async() {
+        let mut buf = Aligned([0u8; 8 + 64]);
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicBool::new(false)),
+                Section::new(64, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let layout = Layout::new::<u64>();
+        let waker = waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let small = allocator.allocate(layout).unwrap();
+        let large = allocator.allocate(layout).unwrap();
+        let mut future = pin!(allocator.allocate_async(layout));
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        // A slot freed in the larger section wakes the future too
+        unsafe { allocator.deallocate(large.cast(), layout) };
+        assert_eq!(WOKEN.load(Ordering::Relaxed), 1);
+        assert!(matches!(future.as_mut().poll(&mut cx), Poll::Ready(Ok(_))));
+
+        // Zero sized allocations take no slab, even with every section full
+        let mut empty = pin!(allocator.allocate_async(Layout::new::<()>()));
+        assert!(matches!(empty.as_mut().poll(&mut cx), Poll::Ready(Ok(_))));
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [1, 1]);
+
+        let mut never = pin!(allocator.allocate_async(Layout::new::<[u8; 65]>()));
+        assert!(matches!(
+            never.as_mut().poll(&mut cx),
+            Poll::Ready(Err(SlabError::NoSectionFits { .. }))
+        ));
+        unsafe { allocator.deallocate(small.cast(), layout) };
+    }
+}
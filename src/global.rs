@@ -0,0 +1,74 @@
+use crate::api::Allocator;
+use crate::SlabAllocator;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+
+// A `'static` allocator can serve as the program's heap through `#[global_allocator]`.
+// Failures are reported as null pointers, as `GlobalAlloc` requires
+unsafe impl<const N: usize> GlobalAlloc for SlabAllocator<'static, N> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.allocate(layout)
+            .map_or(ptr::null_mut(), |slab| slab.cast().as_ptr())
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.allocate_zeroed(layout)
+            .map_or(ptr::null_mut(), |slab| slab.cast().as_ptr())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.deallocate(ptr::NonNull::new_unchecked(ptr), layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: the caller guarantees `new_size` rounded up to the alignment does not overflow
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let ptr = ptr::NonNull::new_unchecked(ptr);
+        let slab = if new_size >= layout.size() {
+            self.grow(ptr, layout, new_layout)
+        } else {
+            self.shrink(ptr, layout, new_layout)
+        };
+        slab.map_or(ptr::null_mut(), |slab| slab.cast().as_ptr())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn global_alloc() {
+        static mut BUF: Aligned<{ 8 * 8 + 64 }> = Aligned([0; 8 * 8 + 64]);
+        // SAFETY: only this test uses the buffer
+        let buf = unsafe { &mut *core::ptr::addr_of_mut!(BUF) };
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(64, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+
+        let layout = Layout::new::<u32>();
+        unsafe {
+            let ptr = allocator.alloc_zeroed(layout);
+            assert_eq!(ptr.cast::<u32>().read(), 0);
+            ptr.cast::<u32>().write(7);
+
+            // Stays in place while the slab fits, then moves
+            assert_eq!(allocator.realloc(ptr, layout, 8), ptr);
+            let moved = allocator.realloc(ptr, Layout::from_size_align(8, 4).unwrap(), 40);
+            assert_ne!(moved, ptr);
+            assert_eq!(moved.cast::<u32>().read(), 7);
+            assert!(allocator.alloc(Layout::new::<[u8; 64]>()).is_null());
+
+            allocator.dealloc(moved, Layout::from_size_align(40, 4).unwrap());
+            assert_eq!(allocator.blocks[1].free_slots(), 1);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use crate::api::{AllocError, Allocator};
+use crate::section::Section;
+use crate::{check_order, place, BufTooSmall, BufferOwner, SlabAllocator};
+use core::sync::atomic::Ordering;
+use core::{alloc, mem, ptr};
+
+/// The layout of the memory taken from the backing allocator to grow a section like `section`,
+/// holding the section and the allocator it came from followed by its slabs, and the offset of
+/// the slabs
+fn grown_layout(section: &Section) -> (alloc::Layout, usize) {
+    alloc::Layout::new::<Grown>()
+        .extend(
+            alloc::Layout::from_size_align(section.capacity(), section.placement())
+                .expect("Section capacity too large"),
+        )
+        .expect("Section capacity too large")
+}
+
+/// The layout of the memory taken from the backing allocator by
+/// [`new_in`](SlabAllocator::new_in) for `blocks`, holding the allocator it is given back to
+/// followed by the sections placed in order, and the offset of the sections
+fn owned_layout(blocks: &[Section]) -> Option<(alloc::Layout, usize)> {
+    let mut len: usize = 0;
+    let mut align = 1;
+    for section in blocks {
+        len = len
+            .checked_next_multiple_of(section.placement())?
+            .checked_add(section.capacity())?;
+        align = align.max(section.placement());
+    }
+    alloc::Layout::new::<&dyn Allocator>()
+        .extend(alloc::Layout::from_size_align(len, align).ok()?)
+        .ok()
+}
+
+/// A section taken from the backing allocator, kept at the start of the memory taken along with
+/// the allocator to give it back to, which may since have been replaced
+#[repr(C)]
+struct Grown<'m> {
+    section: Section,
+    backing: &'m (dyn Allocator + Sync),
+}
+
+/// A section given with [`SlabAllocator::add_region`], kept at the start of the buffer it was
+/// given with
+#[repr(C)]
+struct Region {
+    section: Section,
+    buffer: ptr::NonNull<[u8]>,
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Sets the allocator more sections are taken from when every section able to hold an
+    /// allocation is full. Each new section has the same slabs as the smallest section able to
+    /// hold the allocation. Emptied sections are given back by
+    /// [`release_grown`](SlabAllocator::release_grown) and when the allocator is dropped, each to
+    /// the allocator it was taken from
+    pub fn set_backing(&mut self, backing: &'m (dyn Allocator + Sync)) {
+        self.backing = Some(backing);
+    }
+
+    /// Constructor for [`SlabAllocator`] over a buffer just large enough for `blocks` taken from
+    /// `backing`, such as a page or bump allocator, and given back to it when the allocator is
+    /// dropped. The allocator to give it back to is kept at the start of the buffer.
+    /// Fails with [`AllocError`] where [`new`](SlabAllocator::new) fails for the sections, or if
+    /// `backing` cannot provide the buffer
+    pub fn new_in<A: Allocator + Sync>(
+        blocks: [Section; N],
+        backing: &'m A,
+    ) -> Result<Self, AllocError> {
+        check_order(&blocks).map_err(|_| AllocError)?;
+        let (layout, offset) = owned_layout(&blocks).ok_or(AllocError)?;
+        let chunk = backing.allocate(layout)?;
+        let backing: &'m (dyn Allocator + Sync) = backing;
+        // SAFETY: the chunk starts with space for the reference and is only given back when the
+        // allocator is dropped, within 'm. The buffer keeps the provenance of the whole chunk so
+        // it can be given back
+        let mut allocator = unsafe {
+            chunk.cast::<&(dyn Allocator + Sync)>().write(backing);
+            let buf = ptr::NonNull::slice_from_raw_parts(
+                chunk.cast::<u8>().add(offset),
+                chunk.len() - offset,
+            );
+            Self::over(blocks, buf).expect("Buffer holds the sections")
+        };
+        allocator.buffer_owner = BufferOwner::Backing;
+        Ok(allocator)
+    }
+
+    /// Gives the buffer back to what it was taken from, if the allocator owns it
+    pub(crate) fn free_buffer(&mut self) {
+        match mem::replace(&mut self.buffer_owner, BufferOwner::Borrowed) {
+            BufferOwner::Borrowed => {}
+            BufferOwner::Backing => {
+                let (layout, offset) =
+                    owned_layout(&self.blocks).expect("Layout was valid when taken");
+                // SAFETY: the buffer was carved `offset` bytes into the chunk, which starts with
+                // the allocator it came from, and the allocator is being dropped so no slab is
+                // in use
+                unsafe {
+                    let chunk = self.memory.cast::<u8>().sub(offset);
+                    let backing = chunk.cast::<&(dyn Allocator + Sync)>().read();
+                    backing.deallocate(chunk, layout);
+                }
+            }
+            // SAFETY: the buffer is the box given to `from_box`, and the allocator is being
+            // dropped so no slab is in use
+            #[cfg(feature = "alloc")]
+            BufferOwner::Boxed => unsafe { crate::boxed::free_box(self.memory) },
+        }
+    }
+
+    /// The sections added since the allocator was created, newest first
+    pub(crate) fn extra(&self) -> impl Iterator<Item = &Section> {
+        let mut next = self.extra.load(Ordering::Acquire);
+        core::iter::from_fn(move || {
+            // SAFETY: added sections are only freed through `&mut self`
+            let section = unsafe { next.as_ref()? };
+            next = section.next.load(Ordering::Acquire);
+            Some(section)
+        })
+    }
+
+    /// Every section with its index: the allocator's own by position, then those added since,
+    /// newest first, by the index each was given when added
+    pub(crate) fn indexed(&self) -> impl Iterator<Item = (usize, &Section)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .chain(self.extra().map(|section| (section.index, section)))
+    }
+
+    /// Claims a slab able to hold `layout` from a new section taken from the backing allocator
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub(crate) fn grow_slab(
+        &self,
+        layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let backing = self.backing.ok_or(AllocError)?;
+        let template = &self.blocks[self.section_for_layout(layout).ok_or(AllocError)?];
+        let (chunk_layout, offset) = grown_layout(template);
+        let chunk = backing.allocate(chunk_layout)?.cast::<u8>();
+
+        let mut section = Section {
+            grown: true,
+            ..template.sibling()
+        };
+        // SAFETY: the slabs lie within the chunk, after the section
+        let slabs = unsafe { chunk.add(offset) };
+        place(
+            core::slice::from_mut(&mut section),
+            ptr::NonNull::slice_from_raw_parts(slabs, template.capacity()),
+        )
+        .expect("Chunk holds the section");
+        let slot = section.allocate()?;
+        let grown = chunk.cast::<Grown>();
+        // SAFETY: the chunk starts with space for the section and its backing allocator
+        unsafe { grown.write(Grown { section, backing }) };
+        // SAFETY: the section lives until it is given back through `&mut self`
+        let section = unsafe { self.push_extra(grown.cast()) };
+        Ok(self.claimed(section, slot))
+    }
+
+    /// Brings `buf` into service as an extra section with the slabs of `section`, such as a bank
+    /// of RAM found late in boot. The section itself is kept at the start of `buf`. Panics if it
+    /// has slabs of size zero
+    pub fn add_region(&self, mut section: Section, buf: &'m mut [u8]) -> Result<(), BufTooSmall> {
+        assert!(
+            section.size != 0,
+            "Sections must have slabs of nonzero size"
+        );
+        let memory = ptr::NonNull::from(buf);
+        let header = memory
+            .cast::<u8>()
+            .as_ptr()
+            .align_offset(mem::align_of::<Region>());
+        let start = header + mem::size_of::<Region>();
+        if start > memory.len() {
+            return Err(BufTooSmall {
+                section: 0,
+                required: section.capacity(),
+                shortfall: start + section.capacity() - memory.len(),
+            });
+        }
+        // SAFETY: the slabs are placed after the section, within the buffer
+        let slabs = unsafe { memory.cast::<u8>().add(start) };
+        place(
+            core::slice::from_mut(&mut section),
+            ptr::NonNull::slice_from_raw_parts(slabs, memory.len() - start),
+        )?;
+        // SAFETY: the buffer has room for the section at its aligned start and is borrowed for 'm
+        unsafe {
+            let region = memory.cast::<u8>().add(header).cast::<Region>();
+            region.write(Region {
+                section,
+                buffer: memory,
+            });
+            self.push_extra(region.cast());
+        }
+        Ok(())
+    }
+
+    /// Links the section at `extra` into the allocator's added sections, giving it the index
+    /// after the newest one's so the indices of those already added never change
+    ///
+    /// # Safety
+    /// `extra` must be initialised, not yet shared and live until the allocator unlinks it
+    unsafe fn push_extra(&self, extra: ptr::NonNull<Section>) -> &Section {
+        let mut head = self.extra.load(Ordering::Acquire);
+        loop {
+            (*extra.as_ptr()).index = head.as_ref().map_or(N, |newest| newest.index + 1);
+            let section = extra.as_ref();
+            section.next.store(head, Ordering::Relaxed);
+            match self.extra.compare_exchange_weak(
+                head,
+                extra.as_ptr(),
+                Ordering::Release,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return section,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Gives every section taken from the backing allocator that has no slab allocated back to
+    /// it, returning how many were given back
+    pub fn release_grown(&mut self) -> usize {
+        // SAFETY: only linked sections are passed
+        self.unlink_empty(|section| unsafe { section.as_ref() }.grown)
+    }
+
+    /// Takes every added section that has no slab allocated out of service so a transient
+    /// workload does not pin memory: grown sections are given back to the backing allocator and
+    /// the buffers of regions passed to `reclaim`. Returns how many sections were removed
+    pub fn trim(&mut self, mut reclaim: impl FnMut(&'m mut [u8])) -> usize {
+        self.unlink_empty(|section| {
+            // SAFETY: a section not grown heads the region it was added with, whose buffer is
+            // borrowed for 'm and no longer used by the allocator
+            unsafe {
+                if !section.as_ref().grown {
+                    reclaim(&mut *section.cast::<Region>().as_ref().buffer.as_ptr());
+                }
+            }
+            true
+        })
+    }
+
+    /// Unlinks every added section with no slab allocated that `remove` accepts, giving grown
+    /// ones back to the backing allocator. Returns how many were unlinked
+    fn unlink_empty(&mut self, mut remove: impl FnMut(ptr::NonNull<Section>) -> bool) -> usize {
+        let mut removed = 0;
+        let mut link = &self.extra;
+        // SAFETY: added sections are only freed here and on drop, through `&mut self`
+        while let Some(extra) = ptr::NonNull::new(link.load(Ordering::Relaxed)) {
+            let section = unsafe { extra.as_ref() };
+            if section.free_slots() == section.total_slots() {
+                // Read before `remove`, which may hand a region's memory on
+                let (grown, next) = (section.grown, section.next.load(Ordering::Relaxed));
+                if remove(extra) {
+                    link.store(next, Ordering::Relaxed);
+                    if grown {
+                        // SAFETY: the section is unlinked and none of its slabs are allocated
+                        unsafe { self.free_grown(extra) };
+                    }
+                    removed += 1;
+                    continue;
+                }
+            }
+            link = &section.next;
+        }
+        removed
+    }
+
+    /// Gives every section taken from the backing allocator back to it
+    pub(crate) fn drop_grown(&mut self) {
+        let mut next = *self.extra.get_mut();
+        // SAFETY: the allocator is being dropped so no slab is in use
+        while let Some(extra) = ptr::NonNull::new(next) {
+            let section = unsafe { extra.as_ref() };
+            next = section.next.load(Ordering::Relaxed);
+            if section.grown {
+                unsafe { self.free_grown(extra) };
+            }
+        }
+    }
+
+    /// Gives the memory of the grown section `grown` back to the allocator it was taken from
+    ///
+    /// # Safety
+    /// The section must be unreachable from the allocator and none of its slabs in use
+    unsafe fn free_grown(&self, grown: ptr::NonNull<Section>) {
+        let layout = grown_layout(grown.as_ref()).0;
+        let backing = grown.cast::<Grown>().as_ref().backing;
+        backing.deallocate(grown.cast(), layout);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "std")]
+    extern crate std;
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    #[cfg(feature = "std")]
+    use core::ptr::NonNull;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+    #[cfg(feature = "std")]
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// The system allocator, counting its live allocations
+    #[cfg(feature = "std")]
+    struct System(AtomicUsize);
+
+    #[cfg(feature = "std")]
+    unsafe impl Allocator for System {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            let ptr = NonNull::new(unsafe { std::alloc::alloc(layout) }).ok_or(AllocError)?;
+            Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.fetch_sub(1, Ordering::Relaxed);
+            std::alloc::dealloc(ptr.as_ptr(), layout)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn grow() {
+        let backing = System(AtomicUsize::new(0));
+        let mut buf = Aligned([0u8; 8 * 8 + 32]);
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(32, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        allocator.set_backing(&backing);
+
+        let layout = Layout::new::<[u8; 24]>();
+        let first = allocator.allocate(layout).unwrap();
+        let grown = allocator.allocate(layout).unwrap();
+        assert_eq!(backing.0.load(Ordering::Relaxed), 1);
+        assert_eq!(allocator.layout_of(grown.cast()).unwrap().size, 32);
+        assert!(allocator.allocate(Layout::new::<[u8; 33]>()).is_err());
+
+        assert_eq!(allocator.release_grown(), 0);
+        unsafe {
+            allocator.deallocate(grown.cast(), layout);
+            allocator.deallocate(first.cast(), layout);
+        }
+        assert_eq!(allocator.release_grown(), 1);
+        assert_eq!(backing.0.load(Ordering::Relaxed), 0);
+
+        // Sections still grown when the allocator is dropped are given back too, to the backing
+        // they were taken from
+        let replacement = System(AtomicUsize::new(0));
+        allocator.allocate(layout).unwrap();
+        allocator.allocate(layout).unwrap();
+        allocator.set_backing(&replacement);
+        allocator.allocate(layout).unwrap();
+        assert_eq!(replacement.0.load(Ordering::Relaxed), 1);
+        drop(allocator);
+        assert_eq!(backing.0.load(Ordering::Relaxed), 0);
+        assert_eq!(replacement.0.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn add_region() {
+        let mut buf = Aligned([0u8; 8]);
+        let mut bank = Aligned([0u8; core::mem::size_of::<super::Region>() + 8 * 8]);
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let first = allocator.allocate(layout).unwrap();
+        assert!(allocator.allocate(layout).is_err());
+
+        assert!(allocator
+            .add_region(Section::new(8, AtomicU8::new(0)), &mut bank.0[..16])
+            .is_err());
+        allocator
+            .add_region(Section::new(8, AtomicU8::new(0)), &mut bank.0[..])
+            .unwrap();
+        let second = allocator.allocate(layout).unwrap();
+        assert_eq!(allocator.layout_of(second.cast()).unwrap().section, 1);
+        unsafe { allocator.deallocate(second.cast(), layout) };
+
+        // The emptied region is handed back whole
+        let mut reclaimed = 0;
+        assert_eq!(allocator.trim(|buf| reclaimed += buf.len()), 1);
+        assert_eq!(reclaimed, core::mem::size_of::<super::Region>() + 8 * 8);
+        assert!(allocator.allocate(layout).is_err());
+        unsafe { allocator.deallocate(first.cast(), layout) };
+    }
+
+    #[test]
+    #[should_panic(expected = "nonzero size")]
+    fn add_empty_region() {
+        let mut buf = Aligned([0u8; 8]);
+        let mut bank = Aligned([0u8; core::mem::size_of::<super::Region>() + 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let _ = allocator.add_region(Section::new(0, AtomicU8::new(0)), &mut bank.0[..]);
+    }
+
+    #[test]
+    fn new_in() {
+        let mut buf = Aligned([0u8; 256]);
+        let pages = SlabAllocator::new([Section::new(256, AtomicBool::new(false))], &mut buf.0[..])
+            .unwrap();
+        {
+            let allocator = SlabAllocator::new_in(
+                [
+                    Section::new(1, AtomicU8::new(0)),
+                    Section::new(64, AtomicBool::new(false)).with_align(64),
+                ],
+                &pages,
+            )
+            .unwrap();
+            assert_eq!(pages.gauges()[0].get(), 1);
+            let layout = Layout::new::<[u8; 64]>();
+            let slab = allocator.allocate(layout).unwrap();
+            assert_eq!(slab.cast::<u8>().as_ptr() as usize % 64, 0);
+            unsafe { allocator.deallocate(slab.cast(), layout) };
+            assert!(SlabAllocator::new_in([Section::new(1, AtomicU8::new(0))], &pages).is_err());
+        }
+        assert_eq!(pages.gauges()[0].get(), 0);
+    }
+}
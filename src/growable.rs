@@ -0,0 +1,148 @@
+extern crate alloc;
+
+use crate::api::{AllocError, Allocator};
+use crate::section::Section;
+use crate::{place, sanitize};
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::ptr;
+
+/// Slab allocator whose sections live in a vector, each with its own buffer taken from the heap,
+/// so size classes can be added after observing the workload rather than only at construction.
+/// Sections are kept in increasing order of slab size as they are added
+#[derive(Default)]
+pub struct GrowableSlabAllocator {
+    blocks: Vec<Section>,
+    buffers: Vec<ptr::NonNull<[u8]>>,
+}
+
+// Sections are only modified atomically, and the buffers only through slots claimed in them
+unsafe impl Sync for GrowableSlabAllocator {}
+// The allocator owns its buffers
+unsafe impl Send for GrowableSlabAllocator {}
+
+impl GrowableSlabAllocator {
+    /// Constructor for [`GrowableSlabAllocator`] with no sections
+    pub const fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Adds `section` with a buffer of its own taken from the heap, after the sections with
+    /// slabs no larger. Panics if it has slabs of size zero
+    pub fn add_section(&mut self, mut section: Section) {
+        assert!(
+            section.size != 0,
+            "Sections must have slabs of nonzero size"
+        );
+        let buffer_layout = Layout::from_size_align(section.capacity(), section.placement())
+            .expect("Section capacity too large");
+        // SAFETY: sections have at least one slab of nonzero size
+        let start = unsafe { alloc::alloc::alloc(buffer_layout) };
+        let Some(start) = ptr::NonNull::new(start) else {
+            alloc::alloc::handle_alloc_error(buffer_layout);
+        };
+        let memory = ptr::NonNull::slice_from_raw_parts(start, buffer_layout.size());
+        place(core::slice::from_mut(&mut section), memory).expect("Buffer holds the section");
+        sanitize::created(memory);
+
+        let index = self
+            .blocks
+            .partition_point(|block| block.size <= section.size);
+        self.blocks.insert(index, section);
+        self.buffers.insert(index, memory);
+    }
+
+    /// The sections of the allocator, in increasing order of slab size
+    pub fn sections(&self) -> &[Section] {
+        &self.blocks
+    }
+
+    /// Whether `ptr` points into a slab of this allocator, allocated or not
+    pub fn owns(&self, ptr: ptr::NonNull<u8>) -> bool {
+        self.locate(ptr).is_some()
+    }
+
+    /// The index of the section `ptr` was allocated from and the slot within it
+    fn locate(&self, ptr: ptr::NonNull<u8>) -> Option<(usize, u32)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .find_map(|(index, section)| Some((index, section.slot_of(ptr)?)))
+    }
+}
+
+impl Drop for GrowableSlabAllocator {
+    fn drop(&mut self) {
+        for (section, memory) in self.blocks.iter().zip(&self.buffers) {
+            #[cfg(feature = "sanitize")]
+            sanitize::destroyed(*memory);
+            let buffer_layout = Layout::from_size_align(memory.len(), section.placement())
+                .expect("Layout was valid when allocated");
+            // SAFETY: the buffer was allocated with this layout in `add_section`
+            unsafe { alloc::alloc::dealloc(memory.cast().as_ptr(), buffer_layout) };
+        }
+    }
+}
+
+unsafe impl Allocator for GrowableSlabAllocator {
+    fn allocate(&self, layout: Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Find the smallest size section large enough for the layout
+        let index = self
+            .blocks
+            .iter()
+            .position(|section| section.fits(layout) && section.free_slots() > 0)
+            .ok_or(AllocError)?;
+        let section = &self.blocks[index];
+        let slot = section.allocate()?;
+
+        let slab = section.slot_ptr(slot);
+        sanitize::allocated(self.buffers[index], slab);
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, _layout: Layout) {
+        let (index, slot) = self
+            .locate(ptr)
+            .expect("Could not deallocate slab: could not find section ptr is allocated in");
+        let section = &self.blocks[index];
+
+        let slab = section.slot_ptr(slot);
+        section.clear_freed(slab);
+        sanitize::freed(self.buffers[index], slab);
+        section
+            .deallocate(slot)
+            .expect("Could not deallocate block");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn add_section() {
+        let mut allocator = GrowableSlabAllocator::new();
+        let layout = Layout::new::<[u8; 24]>();
+        assert!(allocator.allocate(layout).is_err());
+
+        allocator.add_section(Section::new(64, AtomicBool::new(false)));
+        let large = allocator.allocate(layout).unwrap();
+        assert_eq!(large.len(), 64);
+        allocator.add_section(Section::new(32, AtomicU8::new(0)).with_align(8));
+        assert_eq!(allocator.sections()[0].size, 32);
+
+        let slab = allocator.allocate(layout).unwrap();
+        assert_eq!(slab.len(), 32);
+        assert!(allocator.owns(large.cast()));
+        unsafe {
+            allocator.deallocate(slab.cast(), layout);
+            allocator.deallocate(large.cast(), layout);
+        }
+        assert_eq!(allocator.sections()[0].free_slots(), 8);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::error::SlabError;
+use crate::section::{Bitmap, Section};
+use crate::{sanitize, SlabAllocator};
+use core::{alloc, fmt, ptr};
+
+/// The bits of a [`Handle`] holding the slot, the section index taking the rest
+const SLOT_BITS: u32 = 8;
+
+/// A compact reference to a slab as the index of its section and its slot within it, rather than
+/// its address, so it stays valid when the buffer is mapped elsewhere and can be stored in
+/// structures persisted to flash
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Handle(u32);
+
+impl Handle {
+    /// The handle of `slot` of the section of index `section`. Panics if the section index does
+    /// not fit in 24 bits or the slot in 8
+    pub const fn new(section: usize, slot: u32) -> Self {
+        assert!(
+            section < 1 << (u32::BITS - SLOT_BITS) && slot < 1 << SLOT_BITS,
+            "Handle out of range"
+        );
+        Self((section as u32) << SLOT_BITS | slot)
+    }
+
+    /// The handle stored as [`to_bits`](Handle::to_bits) returned it
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// The handle as 32 bits to be stored
+    pub const fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    /// The index of the section of the slab
+    pub const fn section(self) -> usize {
+        (self.0 >> SLOT_BITS) as usize
+    }
+
+    /// The slot of the slab within its section
+    pub const fn slot(self) -> u32 {
+        self.0 & ((1 << SLOT_BITS) - 1)
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}:{})", self.section(), self.slot())
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Allocates a slab like [`try_allocate`](SlabAllocator::try_allocate), returning its
+    /// handle rather than its address. Zero sized layouts have no slab and so no handle, failing
+    /// with [`SlabError::NoSectionFits`]
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_handle(&self, layout: alloc::Layout) -> Result<Handle, SlabError> {
+        if layout.size() == 0 {
+            return Err(SlabError::NoSectionFits {
+                size: 0,
+                align: layout.align(),
+            });
+        }
+        let slab = self.try_allocate(layout)?;
+        let (index, _, slot) = self.locate(slab.cast()).expect("Slab was just allocated");
+        Ok(Handle::new(index, slot))
+    }
+
+    /// The slab `handle` refers to, or [`None`] if it is not allocated in this allocator
+    pub fn resolve(&self, handle: Handle) -> Option<ptr::NonNull<[u8]>> {
+        let section = self.section_of(handle)?;
+        (handle.slot() < section.total_slots() && section.is_allocated(handle.slot()))
+            .then(|| section.slot_ptr(handle.slot()))
+    }
+
+    /// Frees the slab `handle` refers to like [`try_free`](SlabAllocator::try_free)
+    ///
+    /// # Safety
+    /// If the slab is allocated, it must have been allocated with `layout` and not be used
+    /// afterwards
+    pub unsafe fn free_handle(
+        &self,
+        handle: Handle,
+        layout: alloc::Layout,
+    ) -> Result<(), SlabError> {
+        let section = self.section_of(handle).ok_or(SlabError::ForeignPointer)?;
+        if handle.slot() >= section.total_slots() {
+            return Err(SlabError::ForeignPointer);
+        }
+        self.try_free(section.slot_ptr(handle.slot()).cast(), layout)
+    }
+
+    /// Moves allocated slabs into the lowest free slots of their section, calling `relocate` with
+    /// the old and new handle of each after its contents are copied, so a fragmented section
+    /// ends up with its free slots together at its end. Sections of [`Section::spanning`] slabs
+    /// are left as they are. Returns how many slabs were moved
+    ///
+    /// # Safety
+    /// Allocated slabs may only be referred to by handle, not by pointer, and no allocation or
+    /// free may run at the same time
+    pub unsafe fn compact(&self, mut relocate: impl FnMut(Handle, Handle)) -> usize {
+        let mut moved = 0;
+        for (index, section) in self.indexed() {
+            // Runs move as a whole and free lists hand out slots in their own order
+            if section.runs.is_some() || section.free_list.is_some() {
+                continue;
+            }
+            loop {
+                let allocated = section.bitmap();
+                let free = !allocated & section.full();
+                if allocated == 0 || free == 0 {
+                    break;
+                }
+                let (to, from) = (
+                    free.trailing_zeros(),
+                    Bitmap::BITS - 1 - allocated.leading_zeros(),
+                );
+                if to > from {
+                    break;
+                }
+                // The allocation keeps its owner, location and tag and is not counted as a new
+                // allocation, only its slot changes
+                let (source, target) = (section.slot_ptr(from), section.slot_ptr(to));
+                sanitize::allocated(self.memory, target);
+                ptr::copy_nonoverlapping(
+                    source.cast::<u8>().as_ptr(),
+                    target.cast::<u8>().as_ptr(),
+                    section.size,
+                );
+                section.carry_meta(from, section, to);
+                section.clear_freed(source);
+                sanitize::freed(self.memory, source);
+                section.move_slot(from, to);
+                relocate(Handle::new(index, from), Handle::new(index, to));
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// The section of the index `handle` names, including those added since the allocator was
+    /// created
+    fn section_of(&self, handle: Handle) -> Option<&Section> {
+        match self.blocks.get(handle.section()) {
+            Some(section) => Some(section),
+            None => self
+                .extra()
+                .find(|section| section.index == handle.section()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn handles() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        let layout = Layout::new::<[u8; 40]>();
+        let handle = allocator.allocate_handle(layout).unwrap();
+        assert_eq!((handle.section(), handle.slot()), (1, 0));
+        assert_eq!(Handle::from_bits(handle.to_bits()), handle);
+
+        let slab = allocator.resolve(handle).unwrap();
+        assert_eq!(slab.len(), 64);
+        assert_eq!(allocator.resolve(Handle::new(0, 0)), None);
+        assert_eq!(allocator.resolve(Handle::new(2, 0)), None);
+        unsafe {
+            assert_eq!(allocator.free_handle(handle, layout), Ok(()));
+            assert!(allocator.free_handle(handle, layout).is_err());
+        }
+        assert_eq!(allocator.resolve(handle), None);
+    }
+
+    #[test]
+    fn added_sections() {
+        let mut buf = Aligned([0u8; 8]);
+        let mut first = Aligned([0u8; 4096]);
+        let mut second = Aligned([0u8; 4096]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        allocator.allocate(layout).unwrap();
+        allocator
+            .add_region(Section::new(8, AtomicBool::new(false)), &mut first.0[..])
+            .unwrap();
+        let handle = allocator.allocate_handle(layout).unwrap();
+        let slab = allocator.resolve(handle).unwrap();
+        assert_eq!(handle.section(), 1);
+
+        // Adding another section leaves the handles into earlier ones pointing at their slabs
+        allocator
+            .add_region(Section::new(8, AtomicBool::new(false)), &mut second.0[..])
+            .unwrap();
+        assert_eq!(allocator.resolve(handle), Some(slab));
+        let other = allocator.allocate_handle(layout).unwrap();
+        assert_eq!(other.section(), 2);
+        assert_ne!(allocator.resolve(other), Some(slab));
+    }
+
+    #[test]
+    fn compact() {
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let mut handles = [Handle::new(0, 0); 8];
+        for (value, handle) in handles.iter_mut().enumerate() {
+            *handle = allocator.allocate_handle(layout).unwrap();
+            let slab = allocator.resolve(*handle).unwrap().cast::<u64>();
+            unsafe { slab.write(value as u64) };
+        }
+        unsafe {
+            for handle in [handles[1], handles[2], handles[5]] {
+                allocator.free_handle(handle, layout).unwrap();
+            }
+            // Slots 7 and 6 move into 1 and 2, leaving the last three free
+            let moved = allocator.compact(|from, to| {
+                let handle = handles.iter_mut().find(|handle| **handle == from).unwrap();
+                *handle = to;
+            });
+            assert_eq!(moved, 2);
+        }
+        assert_eq!(allocator.blocks[0].bitmap(), 0b11111);
+        for value in [0, 3, 4, 6, 7] {
+            let slab = allocator.resolve(handles[value]).unwrap().cast::<u64>();
+            assert_eq!(unsafe { slab.read() }, value as u64);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::section::Section;
+use crate::SlabAllocator;
+
+/// The usage of one section at the time a [`HeapSnapshot`] was taken
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SectionUsage {
+    /// The size of the section's slabs
+    pub size: usize,
+    /// The number of slots in the section
+    pub slots: u32,
+    /// The number of slots unallocated
+    pub free: u32,
+    /// The bytes of slabs allocated
+    pub used_bytes: usize,
+    /// The most slots that have been allocated at once
+    pub peak: u32,
+}
+
+impl SectionUsage {
+    fn of(section: &Section) -> Self {
+        let free = section.free_slots();
+        Self {
+            size: section.size,
+            slots: section.total_slots(),
+            free,
+            used_bytes: (section.total_slots() - free) as usize * section.size,
+            peak: section.peak(),
+        }
+    }
+}
+
+/// The usage of each of an allocator's sections, in integers, taken with
+/// [`heap_snapshot`](SlabAllocator::heap_snapshot)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HeapSnapshot<const N: usize> {
+    /// The usage of each section, in order
+    pub sections: [SectionUsage; N],
+}
+
+impl<const N: usize> HeapSnapshot<N> {
+    /// The number of slots unallocated across every section
+    pub fn free_slots(&self) -> u32 {
+        self.sections.iter().map(|section| section.free).sum()
+    }
+
+    /// The bytes of slabs allocated across every section
+    pub fn used_bytes(&self) -> usize {
+        self.sections.iter().map(|section| section.used_bytes).sum()
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// The usage of each of the allocator's own sections, for telemetry. Sections added since
+    /// the allocator was created are not included
+    pub fn heap_snapshot(&self) -> HeapSnapshot<N> {
+        HeapSnapshot {
+            sections: core::array::from_fn(|index| SectionUsage::of(&self.blocks[index])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+
+    #[test]
+    fn heap_snapshot() {
+        let mut buf = test::buffer();
+        let allocator = SlabAllocator::new(test::sections(0b1), &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let slabs: [_; 3] = core::array::from_fn(|_| allocator.allocate(layout).unwrap());
+        unsafe { allocator.deallocate(slabs[1].cast(), layout) };
+
+        let snapshot = allocator.heap_snapshot();
+        assert_eq!(
+            snapshot.sections[0],
+            heap::SectionUsage {
+                size: 8,
+                slots: 8,
+                free: 5,
+                used_bytes: 24,
+                peak: 4,
+            }
+        );
+        assert_eq!(snapshot.sections[1].peak, 0);
+        assert_eq!(snapshot.free_slots(), 6);
+        assert_eq!(snapshot.used_bytes(), 24);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::SlabAllocator;
+use core::alloc;
+
+/// Functions called on every allocation, free and failed allocation made through an allocator,
+/// such as to feed a profiler or watchdog. Slabs handed out by [`Magazines`](crate::Magazines)
+/// without reaching the allocator are not reported
+#[derive(Debug, Clone, Copy)]
+pub struct Hooks {
+    /// Called with the layout, section index and slot of each slab allocated
+    pub allocated: fn(alloc::Layout, usize, u32),
+    /// Called with the layout, section index and slot of each slab freed
+    pub deallocated: fn(alloc::Layout, usize, u32),
+    /// Called with the layout of each allocation that could not be satisfied
+    pub failed: fn(alloc::Layout),
+}
+
+impl Default for Hooks {
+    /// Hooks that do nothing
+    fn default() -> Self {
+        Self {
+            allocated: |_, _, _| {},
+            deallocated: |_, _, _| {},
+            failed: |_| {},
+        }
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Sets the functions called on every allocation, free and failure
+    pub fn set_hooks(&mut self, hooks: &'m Hooks) {
+        self.hooks = Some(hooks);
+    }
+
+    /// Reports the free of `slot` of section `index`, allocated with `layout`, to the hooks
+    pub(crate) fn hook_deallocated(&self, layout: alloc::Layout, index: usize, slot: u32) {
+        if let Some(hooks) = self.hooks {
+            (hooks.deallocated)(layout, index, slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    static EVENTS: [AtomicUsize; 3] = [const { AtomicUsize::new(0) }; 3];
+
+    #[test]
+    fn hooks() {
+        let mut buf = Aligned([0u8; 64]);
+        let mut allocator =
+            SlabAllocator::new([Section::new(64, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        allocator.set_hooks(&Hooks {
+            allocated: |layout, section, slot| {
+                assert_eq!((layout.size(), section, slot), (8, 0, 0));
+                EVENTS[0].fetch_add(1, Ordering::Relaxed);
+            },
+            deallocated: |layout, section, slot| {
+                assert_eq!((layout.size(), section, slot), (8, 0, 0));
+                EVENTS[1].fetch_add(1, Ordering::Relaxed);
+            },
+            failed: |_| {
+                EVENTS[2].fetch_add(1, Ordering::Relaxed);
+            },
+        });
+
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert!(allocator.allocate(layout).is_err());
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+        assert_eq!(
+            EVENTS.each_ref().map(|count| count.load(Ordering::Relaxed)),
+            [1, 1, 1]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::section::Section;
+use crate::{Event, SlabAllocator};
+use core::fmt;
+use core::panic::Location;
+use core::ptr;
+use core::sync::atomic::Ordering;
+
+/// A slab still allocated when the allocator was checked for leaks.
+/// Displays as `leak: <size> byte slab <section>:<slot> allocated at <file>:<line>:<column>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leak {
+    /// The index of the section the slab is in
+    pub section: usize,
+    /// The slot of the slab within its section
+    pub slot: u32,
+    /// The size of the slab
+    pub size: usize,
+    /// Where the slab was allocated, if known
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "leak: {} byte slab {}:{}",
+            self.size, self.section, self.slot
+        )?;
+        if let Some(location) = self.location {
+            write!(f, " allocated at {location}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Reports every slab that is still allocated to the observer as an [`Event::Leak`],
+    /// returning how many there are. Also run when the allocator is dropped
+    pub fn check_leaks(&self) -> usize {
+        let mut leaks = 0;
+        for (index, section) in self.blocks.iter().enumerate() {
+            for slot in (0..section.total_slots()).filter(|&slot| section.is_allocated(slot)) {
+                leaks += 1;
+                let location = section.meta[slot as usize].location.load(Ordering::Acquire);
+                if let Some(observer) = self.observer {
+                    observer(&Event::Leak(Leak {
+                        section: index,
+                        slot,
+                        size: section.size,
+                        // SAFETY: only ever set from `Location::caller`
+                        location: unsafe { location.as_ref() },
+                    }));
+                }
+            }
+        }
+        leaks
+    }
+
+    #[track_caller]
+    pub(crate) fn record_location(&self, section: &Section, slot: u32) {
+        section.meta[slot as usize].location.store(
+            ptr::from_ref(Location::caller()).cast_mut(),
+            Ordering::Release,
+        );
+    }
+
+    pub(crate) fn forget_location(&self, section: &Section, slot: u32) {
+        section.meta[slot as usize]
+            .location
+            .store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+    static LEAKS: AtomicUsize = AtomicUsize::new(0);
+
+    #[allow(irrefutable_let_patterns)]
+    fn observer(event: &Event<'_>) {
+        if let Event::Leak(leak) = event {
+            assert_eq!((leak.section, leak.slot, leak.size), (0, 1, 8));
+            assert_eq!(leak.location.unwrap().file(), file!());
+            LEAKS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn check_leaks() {
+        let mut buf = Aligned([0u8; 64]);
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        allocator.set_observer(observer);
+
+        let layout = Layout::new::<u64>();
+        let first = allocator.allocate(layout).unwrap();
+        let _leaked = allocator.allocate(layout).unwrap();
+        unsafe { allocator.deallocate(first.cast(), layout) };
+        assert_eq!(allocator.check_leaks(), 1);
+        assert_eq!(LEAKS.load(Ordering::Relaxed), 1);
+
+        drop(allocator);
+        assert_eq!(LEAKS.load(Ordering::Relaxed), 2);
+    }
+}
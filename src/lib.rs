@@ -1,65 +1,955 @@
-#![feature(allocator_api)]
-#![feature(error_in_core)]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+#![cfg_attr(feature = "sanitize", feature(cfg_sanitize))]
 #![warn(missing_docs)]
 #![no_std]
 
 //! A library that implements the [Slab Allocator](https://en.wikipedia.org/wiki/Slab_allocation) using
 //! the rust [allocator_api](https://github.com/rust-lang/rust/issues/32838) ([repo](https://github.com/rust-lang/wg-allocators))
 
+#[cfg(feature = "std")]
+extern crate std;
+
+/// The allocation trait: `core::alloc::Allocator` with the `nightly` feature, a stable copy of
+/// it otherwise
+pub mod api;
+/// Implementation of `allocator-api2`'s allocator trait
+#[cfg(feature = "allocator-api2")]
+mod api2;
+/// Sections of any number of slots, tracked in an array of atomic words
+pub mod array;
+/// Allocation and freeing of many slabs of one layout at once
+mod batch;
+/// Allocation retried with backoff while sections are full
+mod blocking;
+/// Boxes and vectors allocated from an allocator, allocators leaked to the heap and allocators
+/// owning a boxed buffer
+#[cfg(feature = "alloc")]
+pub mod boxed;
+/// Slab backed byte buffers
+pub mod buffer;
+/// Fluent construction of allocators
+pub mod builder;
+/// Caches of constructed objects
+pub mod cache;
+/// Debugging adapter checking the layouts passed to deallocation
+pub mod checked;
+/// Bulk freeing of the allocations made since a checkpoint, or of every allocation
+pub mod checkpoint;
+/// Compile time allocator configuration
+pub mod config;
+/// Fallible deallocation and the handling of pointers that cannot be freed
+pub mod dealloc;
+/// Allocators with a runtime sized list of sections
+pub mod dynamic;
+/// Errors saying why an allocation or free failed
+pub mod error;
+/// Reporting of notable allocator events
+pub mod event;
+/// Slab health reported through the `metrics` facade
+#[cfg(feature = "metrics")]
+pub mod facade;
+/// Policies choosing the section an allocation is served from
+pub mod fit;
+/// Free lists threaded through the free slabs of sections that track their slots in one
+mod freelist;
+/// Futures awaiting a free slab
+#[cfg(feature = "async")]
+pub mod future;
+/// Use of a `'static` allocator as the global allocator
+#[cfg(feature = "global-alloc")]
+pub mod global;
+/// Sections added to a live allocator, grown from a backing allocator or given as extra buffers
+mod grow;
+/// Allocators whose sections live in a vector, so size classes can be added at runtime
+#[cfg(feature = "alloc")]
+pub mod growable;
+/// Compact handles to slabs in place of pointers
+pub mod handle;
+/// Integer snapshots of the usage of every section
+pub mod heap;
+/// Callbacks on every allocation, free and failure
+pub mod hook;
+/// Reporting of slabs still allocated when the allocator is dropped
+#[cfg(feature = "leak-check")]
+pub mod leak;
+/// Single threaded allocator tracking its slots without atomic operations
+pub mod local;
+/// Declaration of allocators in statics
+mod macros;
+/// Per-CPU and per-thread caches of free slots in front of an allocator
+pub mod magazine;
+/// Differential testing against a reference model of the allocator
+#[cfg(feature = "std")]
+pub mod model;
+/// Tracking of which core owns each allocation on multi-core systems
+#[cfg(feature = "amp")]
+pub mod owner;
+/// Export and import of the allocation state, so a heap can be kept across a warm reset
+pub mod persist;
+/// Typed object pools owning their storage
+pub mod pool;
+/// Budgets of bytes and slots for the owners of tagged allocations
+#[cfg(feature = "tags")]
+pub mod quota;
+/// Tentative allocations freed unless committed
+pub mod reserve;
+mod sanitize;
+/// Background scrubbing of free slots
+pub mod scrub;
 /// Types to describe allocation states of slab sizes
 pub mod section;
+/// Allocators confined to one thread
+pub mod single;
+/// Counters of allocator activity
+#[cfg(feature = "stats")]
+pub mod stats;
+/// Aligned backing memory for allocators
+pub mod storage;
+/// Allocator switchable between the slab and the system allocator
+#[cfg(all(feature = "std", feature = "nightly"))]
+pub mod switch;
+/// Accounting of allocations by the tag of the task or subsystem that made them
+#[cfg(feature = "tags")]
+pub mod tag;
+/// Packing of tiny allocations into shared slabs
+pub mod tiny;
+mod trace;
+/// Checking of the allocator's internal invariants
+pub mod verify;
+/// Allocators restricted to some of the sections
+pub mod view;
+/// Prefaulting of the buffer ahead of latency critical work
+#[cfg(feature = "std")]
+pub mod warm;
+/// Notification of sections running low on free slots
+mod watermark;
+pub use api::{AllocError, Allocator};
+pub use array::ArraySection;
+pub use buffer::SlabBuffer;
+pub use builder::{BuildError, SlabAllocatorBuilder};
+pub use cache::{Cached, ObjectCache};
+pub use checked::LayoutChecked;
+pub use checkpoint::Snapshot;
+pub use config::SlabConfig;
 use core::alloc;
+use core::marker;
 use core::ptr;
-pub use section::{Atomics, Section};
+/// The atomics the allocator keeps its state in: core's, or with the `portable-atomic` feature
+/// portable-atomic's, which also work on targets without atomic read-modify-write operations
+#[cfg(not(feature = "portable-atomic"))]
+use core::sync::atomic;
+pub use dealloc::{DeallocError, DeallocPolicy};
+pub use dynamic::DynSlabAllocator;
+pub use error::SlabError;
+pub use event::{Event, Observer};
+pub use fit::FitPolicy;
+#[cfg(feature = "async")]
+pub use future::Allocation;
+#[cfg(feature = "alloc")]
+pub use growable::GrowableSlabAllocator;
+pub use handle::Handle;
+pub use heap::{HeapSnapshot, SectionUsage};
+pub use hook::Hooks;
+pub use local::LocalSlabAllocator;
+pub use magazine::Magazines;
+#[cfg(feature = "std")]
+pub use magazine::ThreadCache;
+pub use persist::StateError;
+pub use pool::{Pool, PoolBox};
+#[cfg(feature = "portable-atomic")]
+use portable_atomic as atomic;
+#[cfg(feature = "tags")]
+pub use quota::Quota;
+pub use reserve::Reservation;
+pub use section::{Atomics, Gauge, Section, SlotInfo};
+pub use single::SingleThread;
+pub use storage::SlabStorage;
+#[cfg(all(feature = "std", feature = "nightly"))]
+pub use switch::{Backend, SlabOrGlobal};
+pub use tiny::TinyPool;
+pub use verify::Corruption;
+pub use view::SlabView;
 
 /// The main struct which encapsulates the allocator.
 /// 'm is the lifetime of the buffer passed and
 /// const N is the number of different slab sizes
 pub struct SlabAllocator<'m, const N: usize> {
     pub(crate) blocks: [Section; N],
-    pub(crate) buffer: [&'m [u8]; N],
+    pub(crate) memory: ptr::NonNull<[u8]>,
+    pub(crate) _buffer: marker::PhantomData<&'m mut [u8]>,
+    pub(crate) observer: Option<Observer>,
+    pub(crate) scrub_cursor: atomic::AtomicUsize,
+    pub(crate) interleave: bool,
+    pub(crate) next_bank: atomic::AtomicUsize,
+    pub(crate) backing: Option<&'m (dyn Allocator + Sync)>,
+    pub(crate) buffer_owner: BufferOwner,
+    pub(crate) extra: atomic::AtomicPtr<Section>,
+    pub(crate) dealloc_policy: DeallocPolicy,
+    pub(crate) fit_policy: FitPolicy,
+    pub(crate) hooks: Option<&'m Hooks>,
+    #[cfg(feature = "oom-dump")]
+    pub(crate) oom_dumped: atomic::AtomicBool,
+    #[cfg(feature = "amp")]
+    pub(crate) core_id: fn() -> u8,
+    #[cfg(feature = "stats")]
+    pub(crate) counters: stats::Counters,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: Option<std::boxed::Box<facade::Metrics>>,
+}
+
+// Sections are only modified atomically, and the buffer only through slots claimed in them
+unsafe impl<'m, const N: usize> Sync for SlabAllocator<'m, N> {}
+// The allocator holds the only borrow of its buffer, and the backing allocator grown sections
+// are returned to is `Sync`, so it can be moved to another thread
+unsafe impl<'m, const N: usize> Send for SlabAllocator<'m, N> {}
+
+/// What the buffer of a [`SlabAllocator`] is given back to when it is dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BufferOwner {
+    /// The buffer is borrowed and outlives the allocator
+    Borrowed,
+    /// The buffer was taken by [`new_in`](SlabAllocator::new_in) from the allocator kept at its
+    /// start
+    Backing,
+    /// The buffer was a box given to [`from_box`](SlabAllocator::from_box)
+    #[cfg(feature = "alloc")]
+    Boxed,
 }
 
-/// Error returned during creation of a [`SlabAllocator`] if the buffer passed is too small
-#[derive(Debug, Clone, Copy)]
-pub struct BufTooSmall;
+/// Error returned during creation of a [`SlabAllocator`] if the buffer passed is too small,
+/// saying which section did not fit and by how much the buffer falls short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BufTooSmall {
+    /// The index of the first section that did not fit
+    pub section: usize,
+    /// The number of bytes that section occupies
+    pub required: usize,
+    /// How many more bytes the buffer needs for every section to fit
+    pub shortfall: usize,
+}
 
 impl core::fmt::Display for BufTooSmall {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "BufTooSmall")
+        write!(
+            f,
+            "buffer too small: section {} needs {} bytes, {} more bytes of buffer are needed",
+            self.section, self.required, self.shortfall
+        )
     }
 }
 
 impl core::error::Error for BufTooSmall {}
 
+/// Error returned during creation of a [`SlabAllocator`] from sections it cannot use or a buffer
+/// that cannot hold them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CreateError {
+    /// The buffer cannot hold every section
+    BufTooSmall(BufTooSmall),
+    /// A section has slabs of size zero
+    ZeroSize {
+        /// The index of the section
+        section: usize,
+    },
+    /// A section has smaller slabs than the section before it
+    Unordered {
+        /// The index of the section
+        section: usize,
+    },
+    /// A section's slot count is not one of 1, 8, 16, 32, 64 or, with the `u128` feature, 128
+    InvalidSlotCount {
+        /// The index of the section
+        section: usize,
+        /// The slot count given
+        slots: u32,
+    },
+}
+
+impl From<BufTooSmall> for CreateError {
+    fn from(error: BufTooSmall) -> Self {
+        Self::BufTooSmall(error)
+    }
+}
+
+impl core::fmt::Display for CreateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::BufTooSmall(error) => error.fmt(f),
+            Self::ZeroSize { section } => write!(f, "section {section} has slabs of size zero"),
+            Self::Unordered { section } => write!(
+                f,
+                "section {section} has smaller slabs than the section before it"
+            ),
+            Self::InvalidSlotCount { section, slots } => write!(
+                f,
+                "section {section} has {slots} slots; {}",
+                section::INVALID_SLOT_COUNT
+            ),
+        }
+    }
+}
+
+impl core::error::Error for CreateError {}
+
+/// Checks `blocks` are in increasing order of slab size with none of size zero, so the first
+/// section able to hold an allocation is the best fit. Sections of the same size are allowed,
+/// as banks to interleave between
+pub(crate) const fn check_order(blocks: &[Section]) -> core::result::Result<(), CreateError> {
+    let mut index = 0;
+    while index < blocks.len() {
+        if blocks[index].size == 0 {
+            return Err(CreateError::ZeroSize { section: index });
+        }
+        if index > 0 && blocks[index - 1].size > blocks[index].size {
+            return Err(CreateError::Unordered { section: index });
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+impl<const N: usize> SlabAllocator<'static, N> {
+    /// Moves the allocator into `slot` for good, returning a `'static` handle to it for APIs
+    /// that need an `Allocator + 'static`, such as spawning into executors, without a heap
+    pub fn leak_into(self, slot: &'static mut core::mem::MaybeUninit<Self>) -> &'static Self {
+        slot.write(self)
+    }
+}
+
+/// The empty allocation handed out for the zero sized `layout`: a dangling pointer on its
+/// alignment
+fn dangling(layout: alloc::Layout) -> ptr::NonNull<[u8]> {
+    // SAFETY: alignments are nonzero
+    let start = unsafe { ptr::NonNull::new_unchecked(ptr::without_provenance_mut(layout.align())) };
+    ptr::NonNull::slice_from_raw_parts(start, 0)
+}
+
+/// The index of the section of `blocks` placed by [`place`] that `ptr` points into and the slot
+/// within it, found by binary search since the sections are placed in order
+pub(crate) fn find_section(blocks: &[Section], ptr: ptr::NonNull<u8>) -> Option<(usize, u32)> {
+    // `ptr` can only be in the last section starting at or before it
+    let index = blocks
+        .partition_point(|section| section.region <= ptr)
+        .checked_sub(1)?;
+    Some((index, blocks[index].slot_of(ptr)?))
+}
+
+/// The offset from `start` the end of `blocks` would be at if they were placed in order from
+/// `offset`
+fn layout_end(blocks: &[Section], start: *const u8, offset: usize) -> usize {
+    blocks.iter().fold(offset, |end, section| {
+        end + start.wrapping_add(end).align_offset(section.placement()) + section.capacity()
+    })
+}
+
+/// Carves the regions of `blocks` out of `memory` in order, each on its alignment
+pub(crate) fn place(
+    blocks: &mut [Section],
+    memory: ptr::NonNull<[u8]>,
+) -> core::result::Result<(), BufTooSmall> {
+    let start = memory.cast::<u8>().as_ptr();
+    let mut offset = 0;
+    for index in 0..blocks.len() {
+        let section = &mut blocks[index];
+        // Place the section on its alignment
+        let aligned = offset + start.wrapping_add(offset).align_offset(section.placement());
+        let size = section.capacity();
+        if aligned > memory.len() || size > memory.len() - aligned {
+            return Err(BufTooSmall {
+                section: index,
+                required: size,
+                shortfall: layout_end(&blocks[index..], start, offset) - memory.len(),
+            });
+        }
+        // SAFETY: the section fits within the rest of the buffer
+        section.region = unsafe { memory.cast::<u8>().add(aligned) };
+        offset = aligned + size;
+        if let Some(list) = &section.free_list {
+            // SAFETY: the section was just placed and none of its slabs are handed out
+            unsafe { list.relink(section) };
+        }
+        if section.zeroed {
+            for slot in 0..section.total_slots() {
+                if !section.is_allocated(slot) {
+                    // SAFETY: the slot lies within the buffer and is not handed out
+                    unsafe {
+                        section
+                            .slot_ptr(slot)
+                            .cast::<u8>()
+                            .write_bytes(0, section.size)
+                    };
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// The number of sections in the allocator
+    pub const SECTIONS: usize = N;
+
     /// Constructor for [`SlabAllocator`] where
     /// `blocks` are the number, sizes and capacity of blocks passed to the allocator and
-    /// `buf` is the memory buffer that the allocator will allocate from
-    pub fn new(
+    /// `buf` is the memory buffer that the allocator will allocate from.
+    /// Fails if the sections are not in increasing order of slab size, one has slabs of size
+    /// zero or the buffer cannot hold them. Sections of the same slab size are accepted rather
+    /// than rejected as duplicates, as banks to
+    /// [interleave](SlabAllocator::set_interleaving) between
+    pub fn new(blocks: [Section; N], buf: &'m mut [u8]) -> core::result::Result<Self, CreateError> {
+        // SAFETY: the buffer is borrowed mutably for 'm
+        unsafe { Self::over(blocks, ptr::NonNull::from(buf)) }
+    }
+
+    /// An allocator over `memory`
+    ///
+    /// # Safety
+    /// `memory` must be valid for reads and writes for 'm and not otherwise accessed for as long
+    unsafe fn over(
+        mut blocks: [Section; N],
+        memory: ptr::NonNull<[u8]>,
+    ) -> core::result::Result<Self, CreateError> {
+        check_order(&blocks)?;
+        place(&mut blocks, memory)?;
+        sanitize::created(memory);
+        trace::created(&blocks, memory.len());
+        Ok(Self::assemble(blocks, memory))
+    }
+
+    /// Constructor for [`SlabAllocator`] over the `len` bytes at `ptr`, for memory no slice
+    /// describes such as SRAM next to MMIO, regions handed over by a bootloader or defined only
+    /// in the linker script. Otherwise as [`new`](SlabAllocator::new)
+    ///
+    /// # Safety
+    /// `ptr` must be non-null and valid for reads and writes of `len` bytes for `'m`, and the
+    /// memory must not be accessed other than through the allocator's slabs for as long
+    pub unsafe fn from_raw_parts(
         blocks: [Section; N],
-        mut buf: &'m mut [u8],
-    ) -> core::result::Result<Self, BufTooSmall> {
-        let mut buffer: [&'m [u8]; N] = [&[]; N];
-        for (index, section) in blocks.iter().enumerate() {
-            let size = match section.allocated {
-                Atomics::Bool(_) => section.size,
-                Atomics::U8(_) => 8 * section.size,
-                Atomics::U16(_) => 16 * section.size,
-                Atomics::U32(_) => 32 * section.size,
-                Atomics::U64(_) => 64 * section.size,
-            };
-            if size > buf.len() {
-                return Err(BufTooSmall);
+        ptr: *mut u8,
+        len: usize,
+    ) -> core::result::Result<Self, CreateError> {
+        Self::new(blocks, core::slice::from_raw_parts_mut(ptr, len))
+    }
+
+    /// Constructor for [`SlabAllocator`] usable in constant expressions, so the allocator can be
+    /// built in a `static`. Sections are placed as if `buf` started on the largest alignment
+    /// they need, as a [`SlabStorage`] with that alignment does; if it does not, slabs are only
+    /// as aligned as their actual addresses allow.
+    /// Panics where [`new`](SlabAllocator::new) fails, or if a section is pre-zeroed or keeps a
+    /// free list since its slabs cannot be written at compile time
+    pub const fn new_const(mut blocks: [Section; N], buf: &'m mut [u8]) -> Self {
+        match check_order(&blocks) {
+            Ok(()) => {}
+            Err(CreateError::ZeroSize { .. }) => panic!("Sections must have slabs of nonzero size"),
+            Err(_) => panic!("Sections must be in increasing order of slab size"),
+        }
+        let len = buf.len();
+        let start = buf.as_mut_ptr();
+        let mut offset: usize = 0;
+        let mut index = 0;
+        while index < N {
+            let section = &mut blocks[index];
+            assert!(
+                !section.zeroed,
+                "Pre-zeroed sections need SlabAllocator::new"
+            );
+            assert!(
+                section.free_list.is_none(),
+                "Free list sections need SlabAllocator::new"
+            );
+            offset = offset.next_multiple_of(section.placement());
+            let size = section.capacity();
+            assert!(
+                offset <= len && size <= len - offset,
+                "Buffer too small for the sections"
+            );
+            // SAFETY: the section fits within the rest of the buffer
+            section.region = unsafe { ptr::NonNull::new_unchecked(start.add(offset)) };
+            offset += size;
+            index += 1;
+        }
+        // SAFETY: the pointer comes from a slice
+        let memory = unsafe { ptr::NonNull::new_unchecked(start) };
+        Self::assemble(blocks, ptr::NonNull::slice_from_raw_parts(memory, len))
+    }
+
+    /// An allocator over `memory` with the already placed `blocks`
+    const fn assemble(blocks: [Section; N], memory: ptr::NonNull<[u8]>) -> Self {
+        Self {
+            blocks,
+            memory,
+            _buffer: marker::PhantomData,
+            observer: None,
+            scrub_cursor: atomic::AtomicUsize::new(0),
+            interleave: false,
+            next_bank: atomic::AtomicUsize::new(0),
+            backing: None,
+            buffer_owner: BufferOwner::Borrowed,
+            extra: atomic::AtomicPtr::new(ptr::null_mut()),
+            dealloc_policy: DeallocPolicy::Panic,
+            fit_policy: FitPolicy::FirstFit,
+            hooks: None,
+            #[cfg(feature = "oom-dump")]
+            oom_dumped: atomic::AtomicBool::new(false),
+            #[cfg(feature = "amp")]
+            core_id: || 0,
+            #[cfg(feature = "stats")]
+            counters: stats::Counters::new(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Constructor for [`SlabAllocator`] with the sections described by the configuration `C`
+    pub fn from_config<C: SlabConfig<N>>(
+        buf: &'m mut [u8],
+    ) -> core::result::Result<Self, CreateError> {
+        Self::new(C::sections(), buf)
+    }
+
+    /// Constructor for [`SlabAllocator`] with a section for each of `layouts`, holding
+    /// `counts` slabs each (one of 1, 8, 16, 32, 64 or, with the `u128` feature, 128).
+    /// Slabs are sized and aligned so each can hold a value with the corresponding layout, so
+    /// `layouts` must be in increasing order of size, none of them zero.
+    /// Otherwise fails as [`new`](SlabAllocator::new) does
+    pub fn for_layouts(
+        layouts: [alloc::Layout; N],
+        counts: [u32; N],
+        buf: &'m mut [u8],
+    ) -> core::result::Result<Self, CreateError> {
+        if let Some(section) = counts
+            .iter()
+            .position(|&slots| Atomics::with_slots(slots).is_none())
+        {
+            return Err(CreateError::InvalidSlotCount {
+                section,
+                slots: counts[section],
+            });
+        }
+        let blocks = core::array::from_fn(|i| {
+            Section::new(
+                layouts[i].size(),
+                Atomics::with_slots(counts[i]).expect("Slot count validated"),
+            )
+            .with_align(layouts[i].align())
+        });
+        Self::new(blocks, buf)
+    }
+
+    /// Alternates allocations between sections with the same slab size, rather than filling the
+    /// first before using the next. Placing such sections in different memory banks spreads
+    /// bus traffic between them
+    pub fn set_interleaving(&mut self, interleave: bool) {
+        self.interleave = interleave;
+    }
+
+    /// Sets the function notified of the allocator's [`Event`]s
+    pub fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+
+    /// Allows the next allocation failure to emit an [`Event::OutOfMemory`] dump again.
+    /// Only the first failure is reported so repeated failures don't flood the observer
+    #[cfg(feature = "oom-dump")]
+    pub fn rearm_oom_dump(&self) {
+        self.oom_dumped.store(false, atomic::Ordering::Relaxed);
+    }
+
+    /// Reports an allocation failure to the observer, once
+    #[cfg(feature = "oom-dump")]
+    fn dump_oom(&self, layout: alloc::Layout) {
+        if let Some(observer) = self.observer {
+            if !self.oom_dumped.swap(true, atomic::Ordering::Relaxed) {
+                observer(&Event::OutOfMemory(event::OomDump {
+                    layout,
+                    sections: &self.blocks,
+                }));
+            }
+        }
+    }
+
+    /// Claims a slab able to hold `layout` from the sections whose index is `allowed`
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub(crate) fn find_slab(
+        &self,
+        layout: alloc::Layout,
+        allowed: impl Fn(usize) -> bool,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let section = self.choose_section(layout, allowed).ok_or(AllocError)?;
+
+        // Mark a slot as allocated and hand out its memory
+        let slot = section.allocate()?;
+
+        Ok(self.claimed(section, slot))
+    }
+
+    /// Claims a run of adjacent slots of a spanning section that together hold `layout`, for an
+    /// allocation too large for any slab
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub(crate) fn find_run(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let size = layout.pad_to_align().size();
+        self.blocks
+            .iter()
+            .filter(|section| section.runs.is_some() && section.slot_align() >= layout.align())
+            .find_map(|section| {
+                let count = u32::try_from(size.div_ceil(section.size)).ok()?;
+                let slot = section.allocate_run(count).ok()?;
+                let slab = self.claimed(section, slot);
+                for rest in slot + 1..slot + count {
+                    sanitize::allocated(self.memory, section.slot_ptr(rest));
+                }
+                Some(ptr::NonNull::slice_from_raw_parts(
+                    slab.cast(),
+                    count as usize * section.size,
+                ))
+            })
+            .ok_or(AllocError)
+    }
+
+    /// The index of the section allocations of `layout` are served from while it has free slots,
+    /// or `None` if no section can hold `layout`
+    pub fn section_for_layout(&self, layout: alloc::Layout) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|section| self.fit_policy.accepts(section, layout))
+    }
+
+    /// Bookkeeping for a slot that has just been claimed, returning its memory
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub(crate) fn claimed(&self, section: &Section, slot: u32) -> ptr::NonNull<[u8]> {
+        #[cfg(feature = "amp")]
+        self.record_owner(section, slot);
+        #[cfg(feature = "leak-check")]
+        self.record_location(section, slot);
+        #[cfg(feature = "tags")]
+        section.meta[slot as usize]
+            .tag
+            .store(0, atomic::Ordering::Relaxed);
+        #[cfg(feature = "stats")]
+        {
+            self.counters.allocated(section.size);
+            section.counters.allocated(section.size);
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.update(&self.blocks, section);
+        }
+        self.check_low(section);
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        if section.free_slots() == 0 {
+            if let Some(index) = self.blocks.iter().position(|other| ptr::eq(other, section)) {
+                trace::exhausted(&self.blocks, index);
+            }
+        }
+
+        let slab = section.slot_ptr(slot);
+        sanitize::allocated(self.memory, slab);
+        slab
+    }
+
+    /// Bookkeeping for a slot about to be freed, clearing its memory
+    ///
+    /// # Safety
+    /// The slot must be allocated and its memory no longer used
+    pub(crate) unsafe fn releasing(&self, section: &Section, slot: u32) {
+        #[cfg(feature = "amp")]
+        self.check_owner(section, slot);
+        #[cfg(feature = "leak-check")]
+        self.forget_location(section, slot);
+
+        let slab = section.slot_ptr(slot);
+        section.clear_freed(slab);
+        sanitize::freed(self.memory, slab);
+    }
+
+    /// Bookkeeping for a slab handed out for `layout` by [`Allocator::allocate`]: the hooks and
+    /// the sizes requested of its section
+    pub(crate) fn served(&self, layout: alloc::Layout, slab: ptr::NonNull<[u8]>) {
+        if !cfg!(feature = "stats") && self.hooks.is_none() {
+            return;
+        }
+        if let Some((index, _section, slot)) = self.locate(slab.cast()) {
+            #[cfg(feature = "stats")]
+            _section.requests.record(layout.size(), slab.len());
+            if let Some(hooks) = self.hooks {
+                (hooks.allocated)(layout, index, slot);
+            }
+        }
+    }
+
+    /// Bookkeeping for a slot of `section` that has just been freed
+    pub(crate) fn released(&self, _section: &Section) {
+        watermark::check_recovered(_section);
+        #[cfg(feature = "async")]
+        future::wake(_section);
+        #[cfg(feature = "stats")]
+        {
+            self.counters.deallocated();
+            _section.counters.deallocated();
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.update(&self.blocks, _section);
+        }
+    }
+
+    /// Bookkeeping for an allocation of `layout` that could not be satisfied
+    pub(crate) fn failed(&self, _layout: alloc::Layout) {
+        trace::failed(&self.blocks, _layout);
+        if let Some(hooks) = self.hooks {
+            (hooks.failed)(_layout);
+        }
+        #[cfg(feature = "oom-dump")]
+        self.dump_oom(_layout);
+        #[cfg(feature = "stats")]
+        {
+            self.counters.failed();
+            // Charge the failure to the size class that ran out
+            if let Some(index) = self.section_for_layout(_layout) {
+                self.blocks[index].counters.failed();
+            }
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.failed();
+        }
+    }
+
+    /// The index of the section `ptr` was allocated from, the section and the slot within it.
+    /// Sections added since the allocator was created follow its own
+    pub(crate) fn locate(&self, ptr: ptr::NonNull<u8>) -> Option<(usize, &Section, u32)> {
+        let base = self.memory.cast::<u8>().as_ptr() as usize;
+        let offset = (ptr.as_ptr() as usize).wrapping_sub(base);
+        if offset >= self.memory.len() {
+            // Added sections never lie within the allocator's own buffer
+            return self
+                .extra()
+                .find_map(|section| Some((section.index, section, section.slot_of(ptr)?)));
+        }
+        let (index, slot) = find_section(&self.blocks, ptr)?;
+        Some((index, &self.blocks[index], slot))
+    }
+
+    /// As [`locate`](SlabAllocator::locate), but first checking the smallest section that fits
+    /// `layout`, so most frees find their section without a scan
+    pub(crate) fn locate_for(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        layout: alloc::Layout,
+    ) -> Option<(usize, &Section, u32)> {
+        let size = layout.pad_to_align().size();
+        let index = self.blocks.partition_point(|section| section.size < size);
+        if let Some(section) = self.blocks.get(index) {
+            if let Some(slot) = section.slot_of(ptr) {
+                return Some((index, section, slot));
+            }
+        }
+        self.locate(ptr)
+    }
+
+    /// Allocates a slab like [`Allocator::allocate`] but returns a
+    /// slice exactly `layout.size()` long rather than the whole slab.
+    /// The whole slab remains reserved until deallocated
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_exact(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let slab = Allocator::allocate(self, layout)?;
+        Ok(ptr::NonNull::slice_from_raw_parts(
+            slab.cast(),
+            layout.size(),
+        ))
+    }
+
+    /// Returns the whole slab of the allocation at `ptr` if it can also hold `new_layout`, so it
+    /// can grow without moving, or fails leaving it untouched
+    ///
+    /// # Safety
+    /// `ptr` must be a live allocation of this allocator
+    pub unsafe fn grow_in_place(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let (_, section, slot) = self
+            .locate(ptr)
+            .expect("Could not grow slab: could not find section ptr is allocated in");
+        if !section.fits(new_layout) {
+            return Err(AllocError);
+        }
+        Ok(section.slot_ptr(slot))
+    }
+
+    /// Moves the allocation at `ptr` to a slab fitting `new_layout`, leaving it in place if its
+    /// current slab already fits
+    ///
+    /// # Safety
+    /// `ptr` must be a live allocation of this allocator made with `old_layout`
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    unsafe fn reallocate(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations have no slab to keep or free
+        if old_layout.size() == 0 {
+            return Allocator::allocate(self, new_layout);
+        }
+        if new_layout.size() == 0 {
+            Allocator::deallocate(self, ptr, old_layout);
+            return Ok(dangling(new_layout));
+        }
+        if let Ok(slab) = self.grow_in_place(ptr, new_layout) {
+            return Ok(slab);
+        }
+
+        let slab = Allocator::allocate(self, new_layout)?;
+        ptr::copy_nonoverlapping(
+            ptr.as_ptr(),
+            slab.cast::<u8>().as_ptr(),
+            old_layout.size().min(new_layout.size()),
+        );
+        // The moved allocation keeps the owner, location and tag of the old one
+        #[cfg(any(feature = "amp", feature = "leak-check", feature = "tags"))]
+        if let (Some((_, source, from)), Some((_, target, to))) = (
+            self.locate_for(ptr, old_layout),
+            self.locate_for(slab.cast(), new_layout),
+        ) {
+            source.carry_meta(from, target, to);
+        }
+        Allocator::deallocate(self, ptr, old_layout);
+        Ok(slab)
+    }
+
+    /// Allocates slot `slot` of section `section` if it is free, for memory that must live at a
+    /// particular address within the buffer. The slab is freed with
+    /// [`Allocator::deallocate`] as usual. Slots of sections with a
+    /// [free list](Section::with_free_list) cannot be allocated this way
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_at(&self, section: usize, slot: u32) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let section = self.blocks.get(section).ok_or(AllocError)?;
+        if slot >= section.total_slots() {
+            return Err(AllocError);
+        }
+        section.claim(slot)?;
+
+        Ok(self.claimed(section, slot))
+    }
+
+    /// Allocates a slab able to hold `layout` from section `section` only, failing rather than
+    /// spilling into a larger section once it is full
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_in_section(
+        &self,
+        section: usize,
+        layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        let slab = self.find_slab(layout, |index| index == section);
+        match slab {
+            Ok(slab) => self.served(layout, slab),
+            Err(_) => self.failed(layout),
+        }
+        slab
+    }
+
+    /// Allocates a slab able to hold `layout` with a bounded number of atomic operations and no
+    /// retry loops, so it can be called from interrupt handlers with hard latency budgets.
+    /// Makes a single attempt at the section [`allocate`](Allocator::allocate) would choose,
+    /// failing if another allocation or free changes it at the same time, and never falls back
+    /// to a backing allocator. Hooks, the observer and enabled logging run as for `allocate`
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_once(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        let Some(section) = self.choose_section(layout, |_| true) else {
+            self.failed(layout);
+            return Err(AllocError);
+        };
+        // Losing a race is not running out of memory, so it is not reported as a failure
+        let slot = section.allocate_once()?;
+        let slab = self.claimed(section, slot);
+        self.served(layout, slab);
+        Ok(slab)
+    }
+
+    /// Whether `ptr` points into a slab of this allocator, allocated or not, so layered
+    /// allocators can tell which allocator to free a pointer with
+    pub fn owns(&self, ptr: ptr::NonNull<u8>) -> bool {
+        self.locate(ptr).is_some()
+    }
+
+    /// Describes the live allocation `ptr` points into, or `None` if `ptr` is not in an
+    /// allocated slab of this allocator
+    pub fn layout_of(&self, ptr: ptr::NonNull<u8>) -> Option<SlotInfo> {
+        let (index, section, slot) = self.locate(ptr)?;
+        if !section.is_allocated(slot) {
+            return None;
+        }
+        Some(SlotInfo {
+            section: index,
+            slot,
+            size: section.size,
+            align: section.slot_align(),
+            #[cfg(feature = "amp")]
+            owner: section.meta[slot as usize]
+                .owner
+                .load(atomic::Ordering::Relaxed),
+            #[cfg(feature = "tags")]
+            tag: section.meta[slot as usize]
+                .tag
+                .load(atomic::Ordering::Relaxed),
+        })
+    }
+
+    /// Returns the buffer the allocator was created with so it can be reused for something else,
+    /// or the allocator itself if any slab is still allocated. A buffer taken by
+    /// [`new_in`](SlabAllocator::new_in) or given as a box is then never freed
+    pub fn into_buffer(mut self) -> core::result::Result<&'m mut [u8], Self> {
+        if self
+            .blocks
+            .iter()
+            .any(|section| section.free_slots() != section.total_slots())
+        {
+            return Err(self);
+        }
+        self.buffer_owner = BufferOwner::Borrowed;
+        // SAFETY: the buffer was borrowed mutably for 'm and no slab of it is in use
+        Ok(unsafe { &mut *self.memory.as_ptr() })
+    }
+
+    /// Frees every slab and overwrites the whole buffer with zeros, apart from the links free
+    /// list sections then thread through their slabs again.
+    /// The writes are volatile so they are not elided even though the memory is never read again
+    pub fn wipe(&mut self) {
+        for section in self.blocks.iter() {
+            section.reset();
+        }
+        sanitize::unpoison(self.memory);
+        let start = self.memory.cast::<u8>();
+        for i in 0..self.memory.len() {
+            // SAFETY: the buffer is exclusively borrowed by the allocator for 'm
+            unsafe { start.add(i).write_volatile(0) };
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        sanitize::poison(self.memory);
+        for section in self.blocks.iter() {
+            if let Some(list) = &section.free_list {
+                // SAFETY: every slab was just freed and the allocator is exclusively borrowed
+                unsafe { list.relink(section) };
             }
-            let (section_block, rest) = buf.split_at_mut(size);
-            buf = rest;
-            buffer[index] = section_block;
         }
-        Ok(Self { blocks, buffer })
     }
 
-    /// The percentage of the capacity that is free for each section
+    /// The percentage of the capacity that is free for each section.
+    /// [`heap_snapshot`](SlabAllocator::heap_snapshot) reports the same in integers
     pub fn percent_free(&self) -> [f32; N] {
         let mut out = [0.0; N];
         out.iter_mut()
@@ -67,49 +957,163 @@ impl<'m, const N: usize> SlabAllocator<'m, N> {
             .for_each(|(arr, section)| *arr = section.percent_free());
         out
     }
+
+    /// The gauges counting the allocated slots of each section, for polling from monitor tasks
+    /// or interrupt handlers
+    pub fn gauges(&self) -> [&Gauge; N] {
+        core::array::from_fn(|i| self.blocks[i].gauge())
+    }
+
+    /// Writes the used and total slots of each section as a terse `used/total,...` list, such as
+    /// `12/32,3/16,0/1`, without allocating; small enough for panic messages and fault handlers
+    pub fn occupancy_str(&self, out: &mut impl core::fmt::Write) -> core::fmt::Result {
+        for (index, section) in self.blocks.iter().enumerate() {
+            if index > 0 {
+                out.write_char(',')?;
+            }
+            write!(
+                out,
+                "{}/{}",
+                section.total_slots() - section.free_slots(),
+                section.total_slots()
+            )?;
+        }
+        Ok(())
+    }
 }
 
-unsafe impl<'m, const N: usize> alloc::Allocator for SlabAllocator<'m, N> {
-    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, alloc::AllocError> {
-        // Target size of block
-        let size = layout.pad_to_align().size();
+impl<'m, const N: usize> core::fmt::Debug for SlabAllocator<'m, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("SlabAllocator ")?;
+        f.debug_list()
+            .entries(self.blocks.iter().chain(self.extra()))
+            .finish()
+    }
+}
 
-        // Find the smallest size section larger than the target size
-        let (index, section) = self
-            .blocks
-            .iter()
-            .enumerate()
-            .find(|(_, section)| section.size >= size && section.free_slots() > 0)
-            .ok_or(alloc::AllocError)?;
+#[cfg(feature = "defmt")]
+impl<'m, const N: usize> defmt::Format for SlabAllocator<'m, N> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "SlabAllocator [");
+        for (index, section) in self.blocks.iter().chain(self.extra()).enumerate() {
+            if index > 0 {
+                defmt::write!(f, ", ");
+            }
+            defmt::write!(f, "{}", section);
+        }
+        defmt::write!(f, "]");
+    }
+}
 
-        // Calculate the offset within the section and mark it as allocated
-        let offset = section.allocate()? as usize;
+impl<'m, const N: usize> Drop for SlabAllocator<'m, N> {
+    fn drop(&mut self) {
+        #[cfg(feature = "leak-check")]
+        self.check_leaks();
+        self.drop_grown();
+        #[cfg(feature = "sanitize")]
+        sanitize::destroyed(self.memory);
+        self.free_buffer();
+    }
+}
 
-        Ok(self.buffer[index][offset..(offset + section.size)].into())
+#[cfg(feature = "zeroize")]
+impl<'m, const N: usize> zeroize::Zeroize for SlabAllocator<'m, N> {
+    fn zeroize(&mut self) {
+        self.wipe();
+    }
+}
+
+unsafe impl<'m, const N: usize> Allocator for SlabAllocator<'m, N> {
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        Ok(self.try_allocate(layout)?)
+    }
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    fn allocate_zeroed(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let slab = Allocator::allocate(self, layout)?;
+        if layout.size() == 0 {
+            return Ok(slab);
+        }
+        let (_, section, _) = self.locate(slab.cast()).expect("Slab is in a section");
+        if !section.zeroed {
+            // SAFETY: the slab was just handed out
+            unsafe { slab.cast::<u8>().write_bytes(0, slab.len()) };
+        }
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        match self.free_slot(self.locate_for(ptr, layout)) {
+            Ok((index, slot)) => self.hook_deallocated(layout, index, slot),
+            Err(error) => self.invalid_free(ptr, error),
+        }
+    }
+
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    unsafe fn grow(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.reallocate(ptr, old_layout, new_layout)
     }
-    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, _layout: alloc::Layout) {
-        // Find section allocated in
-        let (index, buffer) = self
-            .buffer
-            .iter()
-            .enumerate()
-            .find(|(_, s)| s.as_ptr_range().contains(&(ptr.as_ptr() as *const _)))
-            .expect("Could not deallocate slab: could not find section ptr is allocated in");
 
-        // Calculate byte offset in the section
-        let offset = ptr.as_ptr().offset_from(buffer.as_ptr()) as u32;
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        let slab = self.reallocate(ptr, old_layout, new_layout)?;
+        // Zero everything past the old contents, whether or not the slab moved
+        slab.cast::<u8>()
+            .add(old_layout.size())
+            .write_bytes(0, slab.len() - old_layout.size());
+        Ok(slab)
+    }
 
-        // Deallocate the block
-        self.blocks[index]
-            .deallocate(offset)
-            .expect("Could not deallocate block");
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    unsafe fn shrink(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.reallocate(ptr, old_layout, new_layout)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use core::{alloc::Allocator, sync::atomic::*};
+    use core::sync::atomic::*;
+
+    /// Buffer aligned for any of the layouts the tests allocate
+    #[repr(align(64))]
+    pub(crate) struct Aligned<const L: usize>(pub(crate) [u8; L]);
+
+    /// A buffer for the [`sections`]
+    pub(crate) fn buffer() -> Aligned<{ 8 * 8 + 64 }> {
+        Aligned([0; 8 * 8 + 64])
+    }
+
+    /// Eight slabs of 8 bytes, those set in `used` already allocated, and one of 64 bytes
+    pub(crate) fn sections(used: u8) -> [Section; 2] {
+        [
+            Section::new(8, AtomicU8::new(used)),
+            Section::new(64, AtomicBool::new(false)),
+        ]
+    }
+
+    /// An allocator over `buf` with the [`sections`], none of them allocated
+    pub(crate) fn allocator(buf: &mut Aligned<{ 8 * 8 + 64 }>) -> SlabAllocator<'_, 2> {
+        SlabAllocator::new(sections(0), &mut buf.0[..]).unwrap()
+    }
 
     #[test]
     fn initialise() {
@@ -119,6 +1123,26 @@ mod test {
             SlabAllocator::new([Section::new(100, AtomicU8::new(0))], &mut small_buf[..]).is_err()
         );
 
+        let mut buf = Aligned([0u8; 8 * 8 + 40]);
+        let error = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(32, AtomicU8::new(0b11)),
+                Section::new(64, AtomicBool::new(false)).with_align(64),
+            ],
+            &mut buf.0[..],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error,
+            CreateError::BufTooSmall(BufTooSmall {
+                section: 1,
+                required: 8 * 32,
+                shortfall: 8 * 8 + 8 * 32 + 64 - (8 * 8 + 40),
+            })
+        );
+
         let mut large_buf = [0u8; 1024];
         assert!(
             SlabAllocator::new([Section::new(100, AtomicU8::new(0))], &mut large_buf[..]).is_ok()
@@ -131,15 +1155,695 @@ mod test {
     }
 
     #[test]
+    fn send_sync() {
+        fn send<T: Send>() {}
+        fn sync<T: Sync>() {}
+        send::<SlabAllocator<'static, 2>>();
+        sync::<SlabAllocator<'static, 2>>();
+        send::<DynSlabAllocator<'static>>();
+        sync::<DynSlabAllocator<'static>>();
+        send::<LocalSlabAllocator<'static, 2>>();
+        send::<SingleThread<SlabAllocator<'static, 2>>>();
+        send::<ArraySection<'static, 100, 2>>();
+    }
+
+    #[test]
+    fn unordered() {
+        let mut buf = [0u8; 1024];
+        let error = SlabAllocator::new(
+            [
+                Section::new(64, AtomicU8::new(0)),
+                Section::new(8, AtomicU8::new(0)),
+            ],
+            &mut buf[..],
+        )
+        .err();
+        assert_eq!(error, Some(CreateError::Unordered { section: 1 }));
+        // Sections of the same size are banks, not duplicates
+        assert!(SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(8, AtomicU8::new(0)),
+            ],
+            &mut buf[..],
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn zero_size() {
+        let mut buf = [0u8; 1024];
+        let error = SlabAllocator::new([Section::new(0, AtomicU8::new(0))], &mut buf[..]).err();
+        assert_eq!(error, Some(CreateError::ZeroSize { section: 0 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "increasing order of slab size")]
+    fn unordered_const() {
+        let mut buf = [0u8; 1024];
+        let _ = SlabAllocator::new_const([Section::empty(64, 1), Section::empty(8, 8)], &mut buf);
+    }
+
+    #[test]
+    fn new_const() {
+        static mut BUF: Aligned<{ 8 * 8 + 64 }> = Aligned([0; 8 * 8 + 64]);
+        static ALLOCATOR: SlabAllocator<'static, 2> = SlabAllocator::new_const(
+            [Section::empty(8, 8), Section::empty(64, 1)],
+            // SAFETY: only the allocator uses the buffer
+            unsafe { &mut *ptr::addr_of_mut!(BUF.0) },
+        );
+
+        let layout = alloc::Layout::new::<[u8; 40]>();
+        let slab = ALLOCATOR.allocate(layout).unwrap();
+        assert_eq!(slab.len(), 64);
+        assert_eq!(ALLOCATOR.layout_of(slab.cast()).unwrap().section, 1);
+        assert!(ALLOCATOR.allocate(layout).is_err());
+        unsafe { ALLOCATOR.deallocate(slab.cast(), layout) };
+    }
+
+    #[test]
+    fn from_raw_parts() {
+        let mut buf = Aligned([0u8; 64]);
+        let region = buf.0.as_mut_ptr();
+        assert!(unsafe {
+            SlabAllocator::from_raw_parts([Section::new(8, AtomicU8::new(0))], region, 63)
+        }
+        .is_err());
+        let allocator = unsafe {
+            SlabAllocator::from_raw_parts([Section::new(8, AtomicU8::new(0))], region, 64)
+        }
+        .unwrap();
+        let slab = allocator.allocate(alloc::Layout::new::<u64>()).unwrap();
+        assert_eq!(slab.cast::<u8>().as_ptr(), region);
+    }
+
+    #[test]
+    fn leak_into() {
+        static mut BUF: Aligned<64> = Aligned([0; 64]);
+        static mut SLOT: core::mem::MaybeUninit<SlabAllocator<'static, 1>> =
+            core::mem::MaybeUninit::uninit();
+        // SAFETY: only this test uses the buffer and slot
+        let (buf, slot) = unsafe {
+            (
+                &mut *core::ptr::addr_of_mut!(BUF),
+                &mut *core::ptr::addr_of_mut!(SLOT),
+            )
+        };
+        let allocator: &'static SlabAllocator<'static, 1> =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..])
+                .unwrap()
+                .leak_into(slot);
+        assert!(allocator.allocate(alloc::Layout::new::<u64>()).is_ok());
+    }
+
+    #[test]
+    fn for_layouts() {
+        let aligned = alloc::Layout::from_size_align(20, 16).unwrap();
+        let mut buf = [0u8; 1 + 4 * 8 + 15 + 32 * 16];
+        let allocator = SlabAllocator::for_layouts(
+            [alloc::Layout::new::<u32>(), aligned],
+            [8, 16],
+            &mut buf[1..],
+        )
+        .unwrap();
+        assert_eq!(allocator.blocks[0].size, 4);
+        assert_eq!(allocator.blocks[1].size, 32);
+        for section in allocator.blocks.iter() {
+            assert_eq!(section.region.as_ptr() as usize % section.align, 0);
+        }
+
+        let mut small = [0u8; 32 * 16];
+        assert!(SlabAllocator::for_layouts([aligned], [16], &mut small[1..],).is_err());
+        assert_eq!(
+            SlabAllocator::for_layouts([aligned], [12], &mut small[..]).err(),
+            Some(CreateError::InvalidSlotCount {
+                section: 0,
+                slots: 12
+            })
+        );
+        assert_eq!(
+            SlabAllocator::for_layouts([alloc::Layout::new::<()>()], [8], &mut small[..]).err(),
+            Some(CreateError::ZeroSize { section: 0 })
+        );
+    }
+
+    #[test]
+    fn interleaving() {
+        let mut buf = [0u8; 192];
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(8, AtomicU16::new(0)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+        allocator.set_interleaving(true);
+
+        let layout = alloc::Layout::new::<u8>();
+        for _ in 0..4 {
+            allocator.allocate(layout).unwrap();
+        }
+        assert_eq!(allocator.blocks[0].free_slots(), 6);
+        assert_eq!(allocator.blocks[1].free_slots(), 14);
+        for _ in 0..13 {
+            allocator.allocate(layout).unwrap();
+        }
+        assert_eq!(allocator.blocks[0].free_slots(), 0);
+        assert_eq!(allocator.blocks[1].free_slots(), 7);
+    }
+
+    #[test]
+    fn exact() {
+        let mut buf = [0u8; 64];
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf[..]).unwrap();
+
+        let layout = alloc::Layout::new::<[u8; 3]>();
+        assert_eq!(allocator.allocate(layout).unwrap().len(), 8);
+        let exact = allocator.allocate_exact(layout).unwrap();
+        assert_eq!(exact.len(), 3);
+        assert_eq!(allocator.blocks[0].free_slots(), 6);
+
+        unsafe { allocator.deallocate(exact.cast(), layout) };
+        assert_eq!(allocator.blocks[0].free_slots(), 7);
+    }
+
+    #[test]
+    fn deallocate_spilled() {
+        let mut buf = Aligned([0u8; 8 + 64]);
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicBool::new(false)),
+                Section::new(64, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let layout = alloc::Layout::new::<u64>();
+        let first = allocator.allocate(layout).unwrap();
+        let spilled = allocator.allocate(layout).unwrap();
+        assert_eq!(spilled.len(), 64);
+
+        unsafe { allocator.deallocate(spilled.cast(), layout) };
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [1, 0]);
+        unsafe { allocator.deallocate(first.cast(), layout) };
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [0, 0]);
+    }
+
+    #[test]
+    fn reallocate() {
+        let mut buf = buffer();
+        let allocator = allocator(&mut buf);
+
+        // Growing within the slab keeps the allocation in place
+        let small = alloc::Layout::new::<[u8; 2]>();
+        let full = alloc::Layout::new::<[u8; 8]>();
+        let large = alloc::Layout::new::<[u8; 24]>();
+        let slab = allocator.allocate(small).unwrap().cast::<u8>();
+        unsafe {
+            slab.write_bytes(1, 2);
+            let grown = allocator.grow(slab, small, full).unwrap();
+            assert_eq!(grown.cast::<u8>(), slab);
+
+            // Outgrowing it moves to the next section
+            let moved = allocator.grow(slab, full, large).unwrap();
+            assert_ne!(moved.cast::<u8>(), slab);
+            assert_eq!(allocator.blocks[0].free_slots(), 8);
+            assert_eq!(moved.as_ref()[..2], [1, 1]);
+            assert_eq!(allocator.shrink(moved.cast(), large, small).unwrap(), moved);
+        }
+
+        let layout = alloc::Layout::new::<[u8; 4]>();
+        let slab = allocator.allocate(layout).unwrap().cast::<u8>();
+        unsafe {
+            slab.write_bytes(0xff, 8);
+            let grown = allocator
+                .grow_zeroed(slab, layout, alloc::Layout::new::<[u8; 6]>())
+                .unwrap();
+            assert_eq!(grown.as_ref(), [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+            assert!(allocator
+                .grow(slab, layout, alloc::Layout::new::<[u8; 12]>())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn grow_in_place() {
+        let mut buf = [0u8; 8 * 8];
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf[..]).unwrap();
+        let layout = alloc::Layout::new::<[u8; 3]>();
+        let slab = allocator.allocate(layout).unwrap().cast::<u8>();
+
+        unsafe {
+            let grown = allocator
+                .grow_in_place(slab, alloc::Layout::new::<[u8; 8]>())
+                .unwrap();
+            assert_eq!((grown.cast::<u8>(), grown.len()), (slab, 8));
+            assert!(allocator
+                .grow_in_place(slab, alloc::Layout::new::<[u8; 9]>())
+                .is_err());
+            allocator.deallocate(slab, layout);
+        }
+    }
+
+    #[test]
+    fn zero_sized() {
+        let mut buf = Aligned([0u8; 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let empty = alloc::Layout::from_size_align(0, 16).unwrap();
+        let layout = alloc::Layout::new::<u64>();
+
+        let zst = allocator.allocate(empty).unwrap();
+        assert_eq!(zst.len(), 0);
+        assert_eq!(zst.cast::<u8>().as_ptr() as usize % 16, 0);
+        assert_eq!(allocator.blocks[0].free_slots(), 1);
+        unsafe {
+            allocator.deallocate(zst.cast(), empty);
+            let grown = allocator.grow(zst.cast(), empty, layout).unwrap();
+            assert_eq!(allocator.blocks[0].free_slots(), 0);
+            let shrunk = allocator.shrink(grown.cast(), layout, empty).unwrap();
+            assert_eq!(shrunk.len(), 0);
+        }
+        assert_eq!(allocator.blocks[0].free_slots(), 1);
+
+        // Every other way of allocating takes no slab for them either
+        let magazines = Magazines::<1, 1>::new(&allocator, || 0, 1);
+        let mut many = [zst; 3];
+        let zsts = [
+            allocator.allocate_in_section(0, empty).unwrap(),
+            allocator.allocate_once(empty).unwrap(),
+            allocator.view(&[0]).allocate(empty).unwrap(),
+            magazines.allocate(empty).unwrap(),
+        ];
+        assert_eq!(allocator.allocate_many(empty, &mut many), 3);
+        assert!(zsts.iter().chain(&many).all(|zst| zst.is_empty()));
+        assert_eq!(allocator.blocks[0].free_slots(), 1);
+        unsafe {
+            magazines.deallocate(zsts[3].cast(), empty);
+            allocator.deallocate_many(&many.map(|zst| zst.cast()), empty);
+        }
+    }
+
+    #[test]
+    fn allocate_zeroed() {
+        let mut buf = [0xffu8; 8 * 8 + 32];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)).pre_zeroed(),
+                Section::new(32, AtomicBool::new(false)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+
+        for layout in [
+            alloc::Layout::new::<[u8; 8]>(),
+            alloc::Layout::new::<[u8; 32]>(),
+        ] {
+            let slab = allocator.allocate_zeroed(layout).unwrap();
+            unsafe {
+                assert!(slab.as_ref().iter().all(|&byte| byte == 0));
+                slab.cast::<u8>().write_bytes(0xff, slab.len());
+                allocator.deallocate(slab.cast(), layout);
+            }
+            let slab = allocator.allocate_zeroed(layout).unwrap();
+            assert!(unsafe { slab.as_ref() }.iter().all(|&byte| byte == 0));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "poison")]
+    fn poison() {
+        let mut buf = [0u8; 6 + 8 * 8];
+        {
+            let allocator = SlabAllocator::new(
+                [
+                    Section::new(6, AtomicBool::new(false)),
+                    Section::new(8, AtomicU8::new(0)).pre_zeroed(),
+                ],
+                &mut buf[..],
+            )
+            .unwrap();
+
+            for layout in [
+                alloc::Layout::new::<[u8; 6]>(),
+                alloc::Layout::new::<[u8; 8]>(),
+            ] {
+                let slab = allocator.allocate(layout).unwrap();
+                unsafe {
+                    slab.cast::<u8>().write_bytes(0x11, slab.len());
+                    allocator.deallocate(slab.cast(), layout);
+                }
+            }
+        }
+        assert_eq!(buf[..6], [0xde, 0xad, 0xbe, 0xef, 0xde, 0xad]);
+        assert!(buf[6..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn sensitive() {
+        let mut buf = [0u8; 16];
+        {
+            let allocator = SlabAllocator::new(
+                [Section::new(16, AtomicBool::new(false)).sensitive()],
+                &mut buf[..],
+            )
+            .unwrap();
+            let layout = alloc::Layout::new::<[u8; 16]>();
+            let slab = allocator.allocate(layout).unwrap();
+            unsafe {
+                slab.cast::<u8>().write_bytes(0x5a, slab.len());
+                allocator.deallocate(slab.cast(), layout);
+            }
+        }
+        assert_eq!(buf, [0; 16]);
+    }
+
+    #[test]
+    fn allocate_at() {
+        let mut buf = [0u8; 64];
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf[..]).unwrap();
+        let base = allocator.blocks[0].region.as_ptr();
+
+        let slab = allocator.allocate_at(0, 5).unwrap();
+        assert_eq!(slab.cast::<u8>().as_ptr(), base.wrapping_add(40));
+        assert!(allocator.allocate_at(0, 5).is_err());
+        assert!(allocator.allocate_at(0, 8).is_err());
+        assert!(allocator.allocate_at(1, 0).is_err());
+
+        unsafe { allocator.deallocate(slab.cast(), alloc::Layout::new::<u8>()) };
+        assert!(allocator.allocate_at(0, 5).is_ok());
+    }
+
+    #[test]
+    fn allocate_in_section() {
+        let mut buf = buffer();
+        let allocator = SlabAllocator::new(sections(0b1111_1100), &mut buf.0[..]).unwrap();
+        let layout = alloc::Layout::new::<u64>();
+
+        assert!(allocator.allocate_in_section(0, layout).is_ok());
+        assert!(allocator.allocate_in_section(0, layout).is_ok());
+        assert!(allocator.allocate_in_section(0, layout).is_err());
+        assert!(allocator.allocate_in_section(2, layout).is_err());
+        assert!(allocator
+            .allocate_in_section(0, alloc::Layout::new::<[u8; 9]>())
+            .is_err());
+        assert_eq!(allocator.blocks[1].free_slots(), 1);
+        assert_eq!(allocator.allocate_in_section(1, layout).unwrap().len(), 64);
+    }
+
+    #[test]
+    fn allocate_once() {
+        let mut buf = [0u8; 8 * 8];
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0b1111_0000))], &mut buf[..])
+                .unwrap();
+        let layout = alloc::Layout::new::<u64>();
+
+        let slabs: [_; 4] = core::array::from_fn(|_| allocator.allocate_once(layout).unwrap());
+        assert!(allocator.allocate_once(layout).is_err());
+        unsafe { allocator.deallocate(slabs[2].cast(), layout) };
+        assert_eq!(allocator.allocate_once(layout).unwrap(), slabs[2]);
+    }
+
+    #[test]
+    fn alignment() {
+        let mut buf = Aligned([0u8; 1 + 24 * 8]);
+        {
+            let allocator =
+                SlabAllocator::new([Section::new(24, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+            // Slabs 24 bytes apart from a 64 byte aligned start are only 8 byte aligned
+            let slab = allocator.allocate(alloc::Layout::new::<u64>()).unwrap();
+            assert_eq!(slab.cast::<u8>().as_ptr() as usize % 8, 0);
+            let aligned = alloc::Layout::from_size_align(16, 16).unwrap();
+            assert!(allocator.allocate(aligned).is_err());
+            assert_eq!(allocator.section_for_layout(aligned), None);
+        }
+        let allocator =
+            SlabAllocator::new([Section::new(24, AtomicU8::new(0))], &mut buf.0[1..]).unwrap();
+        assert!(allocator.allocate(alloc::Layout::new::<u16>()).is_err());
+        assert!(allocator.allocate(alloc::Layout::new::<[u8; 24]>()).is_ok());
+    }
+
+    #[test]
+    fn boundary() {
+        let mut buf = [0u8; 1 + 63 + 3 * 64 + 48];
+        let allocator = SlabAllocator::new(
+            [Section::new(24, AtomicU8::new(0)).with_boundary(64)],
+            &mut buf[1..],
+        )
+        .unwrap();
+        let base = allocator.blocks[0].region.as_ptr();
+        assert_eq!(base as usize % 64, 0);
+
+        let layout = alloc::Layout::new::<[u8; 24]>();
+        for slot in 0..8 {
+            let slab = allocator.allocate(layout).unwrap();
+            let start = slab.cast::<u8>().as_ptr() as usize;
+            assert_eq!(start / 64, (start + slab.len() - 1) / 64);
+            assert_eq!(allocator.layout_of(slab.cast()).unwrap().slot, slot);
+        }
+        // The gap at the end of a window is not part of any slot
+        assert!(allocator
+            .layout_of(ptr::NonNull::new(base.wrapping_add(48)).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn with_align() {
+        let mut buf = [0u8; 1 + 31 + 8 * 32 + 32];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(24, AtomicU8::new(0)).with_align(32),
+                Section::new(32, AtomicBool::new(false)).with_align(32),
+            ],
+            &mut buf[1..],
+        )
+        .unwrap();
+        assert_eq!(allocator.blocks[0].size, 32);
+
+        let layout = alloc::Layout::from_size_align(24, 32).unwrap();
+        for _ in 0..9 {
+            let slab = allocator.allocate(layout).unwrap();
+            assert_eq!(slab.cast::<u8>().as_ptr() as usize % 32, 0);
+        }
+    }
+
+    #[test]
+    fn coloring() {
+        let mut buf = Aligned([0u8; 7 * 80 + 64]);
+        let allocator = SlabAllocator::new(
+            [Section::new(64, AtomicU8::new(0)).with_coloring(16)],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let base = allocator.blocks[0].region.as_ptr();
+
+        let layout = alloc::Layout::new::<[u8; 64]>();
+        for slot in 0..8 {
+            let slab = allocator.allocate(layout).unwrap();
+            assert_eq!(
+                slab.cast::<u8>().as_ptr() as usize - base as usize,
+                slot * 80
+            );
+        }
+        // The color after a slab is not part of any slot
+        assert!(allocator
+            .layout_of(ptr::NonNull::new(base.wrapping_add(64 + 80)).unwrap())
+            .is_none());
+        assert!(allocator
+            .allocate(alloc::Layout::from_size_align(64, 32).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn layout_of() {
+        let mut buf = [0u8; 64];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicBool::new(false)),
+                Section::new(6, AtomicU8::new(0)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+
+        let layout = alloc::Layout::new::<[u8; 5]>();
+        let slab = allocator.allocate(layout).unwrap().cast::<u8>();
+        let info = allocator.layout_of(slab).unwrap();
+        assert_eq!((info.section, info.slot, info.size), (1, 0, 6));
+        assert_eq!(
+            allocator
+                .layout_of(unsafe { slab.add(5) })
+                .map(|info| info.slot),
+            Some(0)
+        );
+        assert!(allocator.layout_of(unsafe { slab.add(6) }).is_none());
+
+        unsafe { allocator.deallocate(slab, layout) };
+        assert!(allocator.layout_of(slab).is_none());
+        assert!(allocator.owns(slab));
+        assert!(allocator.blocks[1].contains(slab));
+        assert!(!allocator.blocks[0].contains(slab));
+        assert!(!allocator.owns(ptr::NonNull::from(&info).cast()));
+    }
+
+    #[test]
+    fn spanning() {
+        let mut buf = Aligned([0u8; 16 * 8]);
+        let allocator = SlabAllocator::new(
+            [Section::new(16, AtomicU8::new(0)).spanning()],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let large = alloc::Layout::new::<[u8; 40]>();
+        let small = alloc::Layout::new::<[u8; 16]>();
+
+        let run = allocator.allocate(large).unwrap();
+        assert_eq!(run.len(), 48);
+        let slab = allocator.allocate(small).unwrap();
+        assert_eq!(allocator.layout_of(slab.cast()).unwrap().slot, 3);
+        assert!(allocator
+            .allocate(alloc::Layout::new::<[u8; 80]>())
+            .is_err());
+        unsafe { allocator.deallocate(run.cast(), large) };
+        assert_eq!(allocator.gauges()[0].get(), 1);
+
+        // The freed slots no longer continue a run
+        let first = allocator.allocate(small).unwrap();
+        assert_eq!(first.cast(), run.cast::<u8>());
+        let second = allocator.allocate(small).unwrap();
+        unsafe { allocator.deallocate(first.cast(), small) };
+        assert_eq!(allocator.gauges()[0].get(), 2);
+        unsafe {
+            allocator.deallocate(second.cast(), small);
+            allocator.deallocate(slab.cast(), small);
+        }
+    }
+
+    #[test]
+    fn owns() {
+        let mut buf = Aligned([0u8; 16]);
+        let base = ptr::NonNull::from(&mut buf.0).cast::<u8>();
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(1, AtomicBool::new(false)),
+                Section::new(8, AtomicBool::new(false)).with_align(8),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+
+        assert!(allocator.owns(base));
+        // Padding between the sections belongs to neither
+        assert!(!allocator.owns(unsafe { base.add(4) }));
+        assert!(allocator.owns(unsafe { base.add(15) }));
+        assert!(!allocator.owns(unsafe { base.add(16) }));
+    }
+
+    #[test]
+    fn section_for_layout() {
+        let mut buf = Aligned([0u8; 64]);
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicU8::new(0)),
+                Section::new(16, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        assert_eq!(
+            allocator.section_for_layout(alloc::Layout::new::<u16>()),
+            Some(0)
+        );
+        assert_eq!(
+            allocator.section_for_layout(alloc::Layout::new::<u64>()),
+            Some(1)
+        );
+        assert_eq!(
+            allocator.section_for_layout(alloc::Layout::new::<[u8; 17]>()),
+            None
+        );
+
+        // Occupancy does not change the answer
+        let _slab = allocator.allocate(alloc::Layout::new::<u64>()).unwrap();
+        assert_eq!(
+            allocator.section_for_layout(alloc::Layout::new::<u64>()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn occupancy_str() {
+        extern crate std;
+        let mut buf = [0u8; 64];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(2, AtomicU16::new(0)),
+                Section::new(8, AtomicBool::new(false)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+        let _slab = allocator.allocate(alloc::Layout::new::<u8>()).unwrap();
+
+        let mut out = std::string::String::new();
+        allocator.occupancy_str(&mut out).unwrap();
+        assert_eq!(out, "1/16,0/1");
+    }
+
+    #[test]
+    fn into_buffer() {
+        let mut buf = [0u8; 20];
+        let allocator =
+            SlabAllocator::new([Section::new(2, AtomicU8::new(0))], &mut buf[..]).unwrap();
+
+        let layout = alloc::Layout::new::<u8>();
+        let slab = allocator.allocate(layout).unwrap();
+        let allocator = allocator.into_buffer().err().unwrap();
+
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+        let reused = allocator.into_buffer().ok().unwrap();
+        assert_eq!(reused.len(), 20);
+        reused.fill(1);
+        assert_eq!(buf, [1; 20]);
+    }
+
+    #[test]
+    fn wipe() {
+        let mut buf = [0u8; 20];
+        {
+            let mut allocator =
+                SlabAllocator::new([Section::new(2, AtomicU8::new(0))], &mut buf[..]).unwrap();
+
+            for _ in 0..8 {
+                let slab = allocator.allocate(alloc::Layout::new::<u8>()).unwrap();
+                unsafe { slab.cast::<u8>().write(0xFF) };
+            }
+            assert_eq!(allocator.blocks[0].free_slots(), 0);
+
+            allocator.wipe();
+            assert_eq!(allocator.blocks[0].free_slots(), 8);
+        }
+        assert_eq!(buf, [0; 20]);
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
     fn boxes() {
         extern crate std;
-        let mut buf = [0u8; 1024];
+        let mut buf = Aligned([0u8; 1024]);
         let allocator = SlabAllocator::new(
             [Section::new(
                 std::mem::size_of::<std::boxed::Box<u32>>(),
                 AtomicU64::new(0),
             )],
-            &mut buf[..],
+            &mut buf.0[..],
         )
         .expect("Creation of allocator failed");
 
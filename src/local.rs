@@ -0,0 +1,126 @@
+use crate::api::{AllocError, Allocator};
+use crate::section::{Bitmap, Section};
+use crate::{check_order, find_section, place, sanitize, CreateError};
+use core::cell::Cell;
+use core::{alloc, marker, ptr};
+
+/// Slab allocator for a single thread, such as on a single core MCU, tracking its slots in plain
+/// bitmaps read and written without atomic operations. It is not `Sync`, so it cannot be
+/// shared between threads
+pub struct LocalSlabAllocator<'m, const N: usize> {
+    blocks: [Section; N],
+    allocated: [Cell<Bitmap>; N],
+    memory: ptr::NonNull<[u8]>,
+    _buffer: marker::PhantomData<&'m mut [u8]>,
+}
+
+// The allocator holds the only borrow of its buffer, so it can be moved to another thread,
+// though the cells keep it from being shared between them
+unsafe impl<'m, const N: usize> Send for LocalSlabAllocator<'m, N> {}
+
+impl<'m, const N: usize> LocalSlabAllocator<'m, N> {
+    /// Constructor for [`LocalSlabAllocator`] where
+    /// `blocks` are the number, sizes and capacity of blocks passed to the allocator and
+    /// `buf` is the memory buffer that the allocator will allocate from.
+    /// Fails as [`SlabAllocator::new`] does
+    pub fn new(mut blocks: [Section; N], buf: &'m mut [u8]) -> Result<Self, CreateError> {
+        check_order(&blocks)?;
+        let memory = ptr::NonNull::from(buf);
+        place(&mut blocks, memory)?;
+        sanitize::created(memory);
+        Ok(Self {
+            allocated: core::array::from_fn(|index| Cell::new(blocks[index].bitmap())),
+            blocks,
+            memory,
+            _buffer: marker::PhantomData,
+        })
+    }
+
+    /// The number of free slots of each section
+    pub fn free_slots(&self) -> [u32; N] {
+        core::array::from_fn(|index| {
+            self.blocks[index].total_slots() - self.allocated[index].get().count_ones()
+        })
+    }
+
+    /// Whether `ptr` points into a slab of this allocator, allocated or not
+    pub fn owns(&self, ptr: ptr::NonNull<u8>) -> bool {
+        self.locate(ptr).is_some()
+    }
+
+    /// The index of the section `ptr` was allocated from and the slot within it
+    fn locate(&self, ptr: ptr::NonNull<u8>) -> Option<(usize, u32)> {
+        find_section(&self.blocks, ptr)
+    }
+}
+
+#[cfg(feature = "sanitize")]
+impl<'m, const N: usize> Drop for LocalSlabAllocator<'m, N> {
+    fn drop(&mut self) {
+        sanitize::destroyed(self.memory);
+    }
+}
+
+unsafe impl<'m, const N: usize> Allocator for LocalSlabAllocator<'m, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Find the smallest size section large enough for the layout
+        let (section, allocated) = self
+            .blocks
+            .iter()
+            .zip(self.allocated.iter())
+            .find(|(section, allocated)| section.fits(layout) && allocated.get() != section.full())
+            .ok_or(AllocError)?;
+        let bitmap = allocated.get();
+        allocated.set(bitmap | (bitmap + 1));
+
+        let slab = section.slot_ptr((!bitmap).trailing_zeros());
+        sanitize::allocated(self.memory, slab);
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, _layout: alloc::Layout) {
+        let (index, slot) = self
+            .locate(ptr)
+            .expect("Could not deallocate slab: could not find section ptr is allocated in");
+        let (section, allocated) = (&self.blocks[index], &self.allocated[index]);
+        assert!(
+            allocated.get() & (1 << slot) != 0,
+            "Could not deallocate block"
+        );
+
+        let slab = section.slot_ptr(slot);
+        section.clear_freed(slab);
+        sanitize::freed(self.memory, slab);
+        allocated.set(allocated.get() & !(1 << slot));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU64};
+
+    #[test]
+    fn local() {
+        let mut buf = Aligned([0u8; 8 * 64 + 64]);
+        let allocator = LocalSlabAllocator::new(
+            [
+                Section::new(8, AtomicU64::new(0b11)),
+                Section::new(64, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        assert_eq!(allocator.free_slots(), [62, 1]);
+
+        let layout = Layout::new::<u64>();
+        let slabs: [_; 62] = core::array::from_fn(|_| allocator.allocate(layout).unwrap());
+        assert_eq!(allocator.allocate(layout).unwrap().len(), 64);
+        assert!(allocator.allocate(layout).is_err());
+        unsafe { allocator.deallocate(slabs[10].cast(), layout) };
+        assert_eq!(allocator.free_slots(), [1, 0]);
+        assert_eq!(allocator.allocate(layout).unwrap(), slabs[10]);
+    }
+}
@@ -0,0 +1,40 @@
+/// Declares a `static` [`SlabAllocator`](crate::SlabAllocator) named `$name` together with the
+/// buffer it allocates from, given its sections as `size x slots` pairs in increasing order of
+/// size (`slots` being one of 1, 8, 16, 32, 64 or, with the `u128` feature, 128):
+///
+/// ```
+/// slab_alloc::slab_allocator!(ALLOC: [32 x 64, 128 x 16, 1024 x 8]);
+/// assert_eq!(ALLOC.gauges().len(), 3);
+/// ```
+#[macro_export]
+macro_rules! slab_allocator {
+    ($vis:vis $name:ident : [$($size:literal x $slots:literal),+ $(,)?]) => {
+        $vis static $name: $crate::SlabAllocator<'static, { [$($size),+].len() }> = {
+            static mut STORAGE: $crate::SlabStorage<{ 0 $(+ $size * $slots)+ }, 64> =
+                $crate::SlabStorage::new();
+            $crate::SlabAllocator::new_const(
+                [$($crate::Section::empty($size, $slots)),+],
+                // SAFETY: the storage is only reachable through this allocator
+                unsafe { (*::core::ptr::addr_of_mut!(STORAGE)).as_bytes_mut() },
+            )
+        };
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+
+    slab_allocator!(ALLOC: [8 x 8, 64 x 1]);
+
+    #[test]
+    fn slab_allocator() {
+        assert_eq!(ALLOC.gauges().len(), 2);
+        let layout = Layout::new::<[u8; 40]>();
+        let slab = ALLOC.allocate(layout).unwrap();
+        assert_eq!(slab.len(), 64);
+        assert!(ALLOC.allocate(layout).is_err());
+        unsafe { ALLOC.deallocate(slab.cast(), layout) };
+    }
+}
@@ -0,0 +1,264 @@
+use crate::api::{AllocError, Allocator};
+use crate::section::Bitmap;
+use crate::{dangling, DeallocError, SlabAllocator};
+use core::cell::UnsafeCell;
+#[cfg(feature = "std")]
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::{alloc, ptr};
+
+/// The magazines of one CPU, a mask of the slots held for each section, kept on a cache line of
+/// their own
+#[repr(align(64))]
+struct Cpu<const N: usize> {
+    busy: AtomicBool,
+    loaded: UnsafeCell<[Bitmap; N]>,
+}
+
+/// Per-CPU front end to a [`SlabAllocator`] for SMP kernels. Each of `CPUS` CPUs keeps a
+/// magazine of free slots per section, refilled from and drained to the shared section bitmaps
+/// `batch` slots at a time, so most allocations and frees only touch memory local to the CPU.
+/// Slots held in magazines count as allocated in the sections' gauges.
+///
+/// A CPU that re-enters its own magazines, such as from an interrupt handler, falls back to the
+/// shared allocator
+pub struct Magazines<'a, 'm, const N: usize, const CPUS: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    cpu_id: fn() -> usize,
+    batch: u32,
+    cpus: [Cpu<N>; CPUS],
+}
+
+// Each CPU's magazines are only used while holding its busy flag
+unsafe impl<'a, 'm, const N: usize, const CPUS: usize> Sync for Magazines<'a, 'm, N, CPUS> where
+    SlabAllocator<'m, N>: Sync
+{
+}
+
+impl<'a, 'm, const N: usize, const CPUS: usize> Magazines<'a, 'm, N, CPUS> {
+    /// Creates magazines in front of `allocator`, where `cpu_id` returns the index of the CPU
+    /// currently executing and magazines exchange `batch` slots at a time with the sections
+    pub fn new(allocator: &'a SlabAllocator<'m, N>, cpu_id: fn() -> usize, batch: u32) -> Self {
+        assert!(
+            (1..=32).contains(&batch),
+            "Batch size must be between 1 and 32"
+        );
+        Self {
+            allocator,
+            cpu_id,
+            batch,
+            cpus: core::array::from_fn(|_| Cpu {
+                busy: AtomicBool::new(false),
+                loaded: UnsafeCell::new([0; N]),
+            }),
+        }
+    }
+
+    /// Runs `f` on the magazines of the current CPU, or returns `None` if they are in use
+    fn with_magazines<R>(&self, f: impl FnOnce(&mut [Bitmap; N]) -> R) -> Option<R> {
+        let cpu = &self.cpus[(self.cpu_id)() % CPUS];
+        cpu.busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        // SAFETY: the busy flag gives exclusive access to the magazines
+        let result = f(unsafe { &mut *cpu.loaded.get() });
+        cpu.busy.store(false, Ordering::Release);
+        Some(result)
+    }
+}
+
+/// Magazines keyed by thread instead of CPU, giving each of up to `THREADS` threads a cache of
+/// recently freed slabs. Further threads share caches with earlier ones
+#[cfg(feature = "std")]
+pub type ThreadCache<'a, 'm, const N: usize, const THREADS: usize> = Magazines<'a, 'm, N, THREADS>;
+
+/// A small number unique to the calling thread, numbering threads in the order they first call it
+#[cfg(feature = "std")]
+pub fn thread_index() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    std::thread_local! {
+        static INDEX: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    INDEX.with(|index| *index)
+}
+
+#[cfg(feature = "std")]
+impl<'a, 'm, const N: usize, const THREADS: usize> ThreadCache<'a, 'm, N, THREADS> {
+    /// Creates a cache per thread in front of `allocator`, exchanging `batch` slots at a time with
+    /// the sections
+    pub fn per_thread(allocator: &'a SlabAllocator<'m, N>, batch: u32) -> Self {
+        Self::new(allocator, thread_index, batch)
+    }
+}
+
+unsafe impl<'a, 'm, const N: usize, const CPUS: usize> Allocator for Magazines<'a, 'm, N, CPUS> {
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        let Some(index) = self.allocator.section_for_layout(layout) else {
+            return self.allocator.allocate(layout);
+        };
+        let section = &self.allocator.blocks[index];
+        let slot = self.with_magazines(|magazines| {
+            if magazines[index] == 0 {
+                magazines[index] = section.allocate_batch(self.batch);
+            }
+            let loaded = magazines[index];
+            magazines[index] &= loaded.wrapping_sub(1);
+            (loaded != 0).then(|| loaded.trailing_zeros())
+        });
+        match slot.flatten() {
+            Some(slot) => Ok(self.allocator.claimed(section, slot)),
+            // The magazine is in use or the section is empty
+            None => self.allocator.allocate(layout),
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        let Some((index, section, slot)) = self.allocator.locate(ptr) else {
+            return self.allocator.invalid_free(ptr, DeallocError::Foreign);
+        };
+        if !section.is_allocated(slot) {
+            return self.allocator.invalid_free(ptr, DeallocError::NotAllocated);
+        }
+        // Allocations spanning a run of slots were never taken from a magazine
+        if section.run_of(slot).count_ones() > 1 {
+            if let Err(error) = self.allocator.free_slot(Some((index, section, slot))) {
+                self.allocator.invalid_free(ptr, error);
+            }
+            return;
+        }
+
+        self.allocator.releasing(section, slot);
+        let batch = self.batch;
+        // Slabs of sections added since the allocator was created are not kept in magazines
+        let kept = (index < N).then(|| {
+            self.with_magazines(|magazines| {
+                let loaded = magazines[index] | 1 << slot;
+                // Drain a batch back to the section once the magazine holds two
+                if loaded.count_ones() > 2 * batch {
+                    let mut drained = 0;
+                    for _ in 0..batch {
+                        drained |= 1 << (Bitmap::BITS - 1 - (loaded & !drained).leading_zeros());
+                    }
+                    section.deallocate_batch(drained);
+                    magazines[index] = loaded & !drained;
+                } else {
+                    magazines[index] = loaded;
+                }
+            })
+        });
+        if kept.flatten().is_none() && section.deallocate(slot).is_err() {
+            return self.allocator.invalid_free(ptr, DeallocError::NotAllocated);
+        }
+        self.allocator.released(section);
+    }
+}
+
+impl<'a, 'm, const N: usize, const CPUS: usize> Drop for Magazines<'a, 'm, N, CPUS> {
+    fn drop(&mut self) {
+        for cpu in self.cpus.iter_mut() {
+            for (section, loaded) in self.allocator.blocks.iter().zip(cpu.loaded.get_mut()) {
+                section.deallocate_batch(*loaded);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+    static CPU: AtomicUsize = AtomicUsize::new(0);
+
+    fn cpu_id() -> usize {
+        CPU.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn magazines() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        let gauge = allocator.gauges()[0];
+        {
+            let magazines = Magazines::<2, 2>::new(&allocator, cpu_id, 2);
+            let layout = Layout::new::<u64>();
+
+            let first = magazines.allocate(layout).unwrap();
+            assert_eq!(gauge.get(), 2);
+            let second = magazines.allocate(layout).unwrap();
+            assert_eq!(gauge.get(), 2);
+
+            CPU.store(1, Ordering::Relaxed);
+            let slabs: [_; 6] = core::array::from_fn(|_| magazines.allocate(layout).unwrap());
+            assert_eq!(gauge.get(), 8);
+            // With the section exhausted allocations move on to the next one
+            let large = magazines.allocate(layout).unwrap();
+            assert_eq!(large.len(), 64);
+
+            unsafe {
+                magazines.deallocate(large.cast(), layout);
+                magazines.deallocate(first.cast(), layout);
+                magazines.deallocate(second.cast(), layout);
+                for slab in slabs {
+                    magazines.deallocate(slab.cast(), layout);
+                }
+            }
+            // Frees beyond two batches went back to the section
+            assert_eq!(gauge.get(), 4);
+        }
+        assert_eq!(gauge.get(), 0);
+    }
+
+    #[test]
+    fn spanning() {
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let allocator = SlabAllocator::new(
+            [Section::new(8, AtomicU8::new(0)).spanning()],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let gauge = allocator.gauges()[0];
+        {
+            let magazines = Magazines::<1, 2>::new(&allocator, cpu_id, 2);
+            let layout = Layout::new::<[u8; 32]>();
+            let run = magazines.allocate(layout).unwrap();
+            assert_eq!(gauge.get(), 4);
+            // The whole run goes back to the section rather than its first slot to a magazine
+            unsafe { magazines.deallocate(run.cast(), layout) };
+            assert_eq!(gauge.get(), 0);
+        }
+        assert_eq!(gauge.get(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn thread_cache() {
+        extern crate std;
+        use crate::magazine::{thread_index, ThreadCache};
+
+        let index = thread_index();
+        assert_eq!(thread_index(), index);
+        assert_ne!(std::thread::spawn(thread_index).join().unwrap(), index);
+
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let cache = ThreadCache::<1, 4>::per_thread(&allocator, 4);
+        let layout = Layout::new::<u64>();
+        let slab = cache.allocate(layout).unwrap();
+        unsafe { cache.deallocate(slab.cast(), layout) };
+        // The freed slab stays cached for the thread and is handed out again
+        assert_eq!(allocator.gauges()[0].get(), 4);
+        assert_eq!(cache.allocate(layout).unwrap(), slab);
+    }
+}
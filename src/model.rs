@@ -0,0 +1,186 @@
+use crate::api::Allocator;
+use crate::{FitPolicy, SlabAllocator};
+use core::alloc::Layout;
+use core::ptr;
+use std::collections::BTreeSet;
+use std::vec::Vec;
+
+/// An operation applied to both an allocator and the [`Model`] by [`check`]
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    /// Allocate with the given layout
+    Allocate(Layout),
+    /// Free a live allocation, chosen by index modulo the number of live allocations.
+    /// Does nothing if there are none
+    Deallocate(usize),
+}
+
+/// A live allocation: its slab, layout and the section and slot the model gave it
+type Live = (ptr::NonNull<[u8]>, Layout, (usize, u32));
+
+/// An obviously correct model of the allocation policy: the set of free slots of each size class
+pub struct Model {
+    sections: Vec<(usize, usize, BTreeSet<u32>)>,
+    policy: FitPolicy,
+}
+
+impl Model {
+    /// A model of `allocator` with every slot free
+    pub fn new<const N: usize>(allocator: &SlabAllocator<'_, N>) -> Self {
+        Self {
+            sections: allocator
+                .blocks
+                .iter()
+                .map(|section| {
+                    // The alignment every slot of the section has
+                    let align = (0..section.total_slots())
+                        .map(|slot| {
+                            let address = section.slot_ptr(slot).cast::<u8>().as_ptr() as usize;
+                            1 << address.trailing_zeros()
+                        })
+                        .min()
+                        .unwrap();
+                    (section.size, align, (0..section.total_slots()).collect())
+                })
+                .collect(),
+            policy: allocator.fit_policy,
+        }
+    }
+
+    /// The section, slot and slab size an allocation of `layout` is served from
+    pub fn allocate(&mut self, layout: Layout) -> Option<(usize, u32, usize)> {
+        let size = layout.pad_to_align().size();
+        let policy = self.policy;
+        let mut candidates =
+            self.sections
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, (slab, align, free))| {
+                    *slab >= size
+                        && *align >= layout.align()
+                        && !free.is_empty()
+                        && (policy != FitPolicy::ExactFit || *slab == size)
+                });
+        let (index, (slab, _, free)) = if policy == FitPolicy::BestFit {
+            candidates.min_by_key(|(_, (slab, _, free))| (*slab, free.len()))?
+        } else {
+            candidates.next()?
+        };
+        let slot = free.pop_first()?;
+        Some((index, slot, *slab))
+    }
+
+    /// Frees `slot` of section `index`
+    pub fn deallocate(&mut self, index: usize, slot: u32) {
+        assert!(self.sections[index].2.insert(slot), "Double free in model");
+    }
+}
+
+/// Runs `ops` against `allocator`, which must have nothing allocated, and a [`Model`] of it,
+/// panicking if they disagree on whether an allocation succeeds or on its slab size, or if live
+/// allocations overlap. Everything is freed again afterwards
+pub fn check<const N: usize>(allocator: &SlabAllocator<'_, N>, ops: &[Op]) {
+    let mut model = Model::new(allocator);
+    let mut live: Vec<Live> = Vec::new();
+
+    for (step, op) in ops.iter().enumerate() {
+        match *op {
+            // Zero sized allocations take no slot, so the model has nothing to track
+            Op::Allocate(layout) if layout.size() == 0 => {
+                let slab = allocator.allocate(layout);
+                assert!(
+                    slab.is_ok_and(|slab| slab.is_empty()),
+                    "Step {step}: zero sized allocation was not empty"
+                );
+            }
+            Op::Allocate(layout) => match (allocator.allocate(layout), model.allocate(layout)) {
+                (Ok(slab), Some((index, slot, size))) => {
+                    assert_eq!(
+                        slab.len(),
+                        size,
+                        "Step {step}: slab size differs from model"
+                    );
+                    let start = slab.cast::<u8>().as_ptr() as usize;
+                    for (other, _, _) in live.iter() {
+                        let other_start = other.cast::<u8>().as_ptr() as usize;
+                        assert!(
+                            start + slab.len() <= other_start || other_start + other.len() <= start,
+                            "Step {step}: allocation overlaps a live allocation"
+                        );
+                    }
+                    live.push((slab, layout, (index, slot)));
+                }
+                (Err(_), None) => {}
+                (real, model) => panic!(
+                    "Step {step}: allocator {} but model {}",
+                    if real.is_ok() { "succeeded" } else { "failed" },
+                    if model.is_some() {
+                        "succeeded"
+                    } else {
+                        "failed"
+                    },
+                ),
+            },
+            Op::Deallocate(i) => {
+                if live.is_empty() {
+                    continue;
+                }
+                let (slab, layout, (index, slot)) = live.swap_remove(i % live.len());
+                // SAFETY: the slab is live and was allocated with `layout`
+                unsafe { allocator.deallocate(slab.cast(), layout) };
+                model.deallocate(index, slot);
+            }
+        }
+    }
+
+    for (slab, layout, _) in live {
+        // SAFETY: the slab is live and was allocated with `layout`
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU8};
+
+    #[test]
+    fn differential() {
+        let mut buf = [0u8; 8 * 16 + 32 * 8 + 128];
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU16::new(0)),
+                Section::new(32, AtomicU8::new(0)),
+                Section::new(128, AtomicBool::new(false)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+
+        // Deterministic pseudo random operations
+        let mut state = 0x2545_f491_u32;
+        let ops: Vec<Op> = (0..2000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                if state.is_multiple_of(3) {
+                    Op::Deallocate(state as usize / 3)
+                } else {
+                    Op::Allocate(
+                        Layout::from_size_align(state as usize % 160, 1 << (state % 5)).unwrap(),
+                    )
+                }
+            })
+            .collect();
+        for policy in [FitPolicy::FirstFit, FitPolicy::BestFit, FitPolicy::ExactFit] {
+            allocator.set_fit_policy(policy);
+            check(&allocator, &ops);
+        }
+        assert!(allocator
+            .blocks
+            .iter()
+            .all(|section| section.free_slots() == section.total_slots()));
+    }
+}
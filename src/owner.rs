@@ -0,0 +1,93 @@
+use crate::section::Section;
+use crate::SlabAllocator;
+use core::ptr;
+use core::sync::atomic::Ordering;
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Sets the function returning the id of the core currently executing.
+    /// Every allocation records the core it was made on, and in debug builds
+    /// deallocating from any other core panics
+    pub fn set_core_id(&mut self, core_id: fn() -> u8) {
+        self.core_id = core_id;
+    }
+
+    /// The core that owns the allocation `ptr` points into
+    pub fn owner_of(&self, ptr: ptr::NonNull<u8>) -> Option<u8> {
+        let (_, section, slot) = self.locate(ptr)?;
+        Some(section.meta[slot as usize].owner.load(Ordering::Relaxed))
+    }
+
+    /// Hands ownership of the allocation `ptr` points into over to `core`,
+    /// which may then deallocate it
+    pub fn transfer(&self, ptr: ptr::NonNull<u8>, core: u8) {
+        let (_, section, slot) = self
+            .locate(ptr)
+            .expect("Could not transfer slab: could not find section ptr is allocated in");
+        section.meta[slot as usize]
+            .owner
+            .store(core, Ordering::Release);
+    }
+
+    pub(crate) fn record_owner(&self, section: &Section, slot: u32) {
+        section.meta[slot as usize]
+            .owner
+            .store((self.core_id)(), Ordering::Release);
+    }
+
+    pub(crate) fn check_owner(&self, section: &Section, slot: u32) {
+        let owner = section.meta[slot as usize].owner.load(Ordering::Acquire);
+        let core = (self.core_id)();
+        debug_assert_eq!(
+            owner, core,
+            "Slab owned by core {} deallocated from core {}",
+            owner, core
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    static CORE: AtomicU8 = AtomicU8::new(0);
+
+    fn core_id() -> u8 {
+        CORE.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn transfer() {
+        let mut buf = Aligned([0u8; 64]);
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        allocator.set_core_id(core_id);
+
+        let layout = Layout::new::<u64>();
+        let ptr = allocator.allocate(layout).unwrap().cast();
+        assert_eq!(allocator.owner_of(ptr), Some(0));
+
+        allocator.transfer(ptr, 1);
+        assert_eq!(allocator.owner_of(ptr), Some(1));
+        CORE.store(1, Ordering::Relaxed);
+        unsafe { allocator.deallocate(ptr, layout) };
+    }
+
+    #[test]
+    #[should_panic(expected = "Slab owned by core 0 deallocated from core 2")]
+    fn foreign_free() {
+        fn other_core() -> u8 {
+            2
+        }
+        let mut buf = Aligned([0u8; 64]);
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+
+        let layout = Layout::new::<u64>();
+        let ptr = allocator.allocate(layout).unwrap().cast();
+        allocator.set_core_id(other_core);
+        unsafe { allocator.deallocate(ptr, layout) };
+    }
+}
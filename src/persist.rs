@@ -0,0 +1,195 @@
+use crate::section::Bitmap;
+use crate::{sanitize, SlabAllocator};
+use core::fmt;
+
+/// The first bytes of exported state, naming the format and its version
+const MAGIC: [u8; 4] = *b"SLB2";
+
+/// The bytes of exported state describing one section: its slab size as a little endian `u64`
+/// followed by its allocation bitmap and the bitmap of slots continuing a run, each as a little
+/// endian `u128`
+const RECORD_LEN: usize = 8 + 16 + 16;
+
+/// Why exported state could not be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StateError {
+    /// The data, or the buffer it is exported to, is shorter than
+    /// [`STATE_LEN`](SlabAllocator::STATE_LEN)
+    Truncated,
+    /// The data was not exported by [`export_state`](SlabAllocator::export_state)
+    Format,
+    /// The section of this index has a different slab size, fewer slots or does not span runs
+    /// unlike the one exported
+    Mismatch(usize),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "state is truncated"),
+            Self::Format => write!(f, "state is not in a known format"),
+            Self::Mismatch(index) => write!(f, "section {index} does not match the state"),
+        }
+    }
+}
+
+impl core::error::Error for StateError {}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// The number of bytes [`export_state`](SlabAllocator::export_state) writes
+    pub const STATE_LEN: usize = MAGIC.len() + N * RECORD_LEN;
+
+    /// Writes which slabs are allocated to the start of `out` in a format stable across builds
+    /// and targets, returning the number of bytes written. Sections added since the allocator
+    /// was created are not recorded
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::useless_conversion)]
+    pub fn export_state(&self, out: &mut [u8]) -> Result<usize, StateError> {
+        let out = out
+            .get_mut(..Self::STATE_LEN)
+            .ok_or(StateError::Truncated)?;
+        let (magic, records) = out.split_at_mut(MAGIC.len());
+        magic.copy_from_slice(&MAGIC);
+        for (section, record) in self.blocks.iter().zip(records.chunks_exact_mut(RECORD_LEN)) {
+            let (size, fields) = record.split_at_mut(8);
+            let (bitmap, runs) = fields.split_at_mut(16);
+            size.copy_from_slice(&(section.size as u64).to_le_bytes());
+            bitmap.copy_from_slice(&u128::from(section.bitmap()).to_le_bytes());
+            runs.copy_from_slice(&u128::from(section.run_bits()).to_le_bytes());
+        }
+        Ok(Self::STATE_LEN)
+    }
+
+    /// Marks exactly the slabs recorded in `data` by
+    /// [`export_state`](SlabAllocator::export_state) as allocated, so a heap kept in
+    /// battery-backed RAM survives a warm reset with its allocations intact. Nothing is changed
+    /// if the state does not match the allocator's sections
+    ///
+    /// # Safety
+    /// The allocator must have been created over the same memory with the same sections, none of
+    /// them pre-zeroed, and no slab allocated before the import may be used afterwards. No
+    /// allocation or free may run at the same time
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast)]
+    pub unsafe fn import_state(&self, data: &[u8]) -> Result<(), StateError> {
+        let data = data.get(..Self::STATE_LEN).ok_or(StateError::Truncated)?;
+        let (magic, records) = data.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(StateError::Format);
+        }
+        let mut bitmaps: [(Bitmap, Bitmap); N] = [(0, 0); N];
+        for (index, record) in records.chunks_exact(RECORD_LEN).enumerate() {
+            let section = &self.blocks[index];
+            let (size, fields) = record.split_at(8);
+            let (bitmap, runs) = fields.split_at(16);
+            let size = u64::from_le_bytes(size.try_into().expect("Record holds the size"));
+            let bitmap = u128::from_le_bytes(bitmap.try_into().expect("Record holds the bitmap"));
+            let runs = u128::from_le_bytes(runs.try_into().expect("Record holds the runs"));
+            // Runs continue allocated slots after the first, in sections that span them
+            if size != section.size as u64
+                || bitmap & !(section.full() as u128) != 0
+                || runs & !(bitmap & bitmap << 1) != 0
+                || (runs != 0 && section.runs.is_none())
+            {
+                return Err(StateError::Mismatch(index));
+            }
+            bitmaps[index] = (bitmap as Bitmap, runs as Bitmap);
+        }
+
+        self.reset();
+        for (section, (bitmap, runs)) in self.blocks.iter().zip(bitmaps) {
+            section.restore(bitmap, runs);
+            let mut slots = bitmap;
+            while slots != 0 {
+                let slot = slots.trailing_zeros();
+                sanitize::allocated(self.memory, section.slot_ptr(slot));
+                slots &= slots - 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn export_import() {
+        let mut buf = test::buffer();
+        let mut state = [0u8; SlabAllocator::<2>::STATE_LEN];
+        let sections = || test::sections(0);
+        let layout = Layout::new::<u64>();
+        {
+            let allocator = SlabAllocator::new(sections(), &mut buf.0[..]).unwrap();
+            allocator.allocate(layout).unwrap();
+            let slab = allocator.allocate(layout).unwrap().cast::<u64>();
+            unsafe { slab.write(7) };
+            assert!(allocator.export_state(&mut state[..10]).is_err());
+            assert_eq!(allocator.export_state(&mut state).unwrap(), state.len());
+        }
+
+        // After a warm reset
+        let allocator = SlabAllocator::new(sections(), &mut buf.0[..]).unwrap();
+        unsafe {
+            assert_eq!(
+                allocator.import_state(&state[..10]),
+                Err(StateError::Truncated)
+            );
+            assert_eq!(
+                allocator.import_state(&[0; SlabAllocator::<2>::STATE_LEN]),
+                Err(StateError::Format)
+            );
+            let mut other = state;
+            other[4] = 16;
+            assert_eq!(allocator.import_state(&other), Err(StateError::Mismatch(0)));
+            allocator.import_state(&state).unwrap();
+        }
+        assert_eq!(allocator.gauges().map(|gauge| gauge.get()), [2, 0]);
+        let slab = allocator.blocks[0].slot_ptr(1).cast::<u64>();
+        assert!(allocator.layout_of(slab.cast()).is_some());
+        assert_eq!(unsafe { slab.read() }, 7);
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+    }
+
+    #[test]
+    fn runs() {
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let mut state = [0u8; SlabAllocator::<1>::STATE_LEN];
+        let layout = Layout::new::<[u8; 32]>();
+        {
+            let allocator = SlabAllocator::new(
+                [Section::new(8, AtomicU8::new(0)).spanning()],
+                &mut buf.0[..],
+            )
+            .unwrap();
+            allocator.allocate(layout).unwrap();
+            allocator.export_state(&mut state).unwrap();
+        }
+
+        // Runs cannot be restored into a section that does not span them
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        assert_eq!(
+            unsafe { allocator.import_state(&state) },
+            Err(StateError::Mismatch(0))
+        );
+        drop(allocator);
+
+        let allocator = SlabAllocator::new(
+            [Section::new(8, AtomicU8::new(0)).spanning()],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        unsafe { allocator.import_state(&state).unwrap() };
+        assert_eq!(allocator.gauges()[0].get(), 4);
+        // The whole run is freed, not only its first slot
+        let run = allocator.blocks[0].slot_ptr(0).cast::<u8>();
+        unsafe { allocator.deallocate(run, layout) };
+        assert_eq!(allocator.gauges()[0].get(), 0);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::section::Section;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ops;
+
+/// Pool of `N` objects of type `T` (`N` being one of 1, 8, 16, 32, 64 or, with the `u128`
+/// feature, 128) owning its storage, handing out [`PoolBox`]es that return their slot when
+/// dropped. Slots are claimed in a section's atomic bitmap, so the pool can be shared between
+/// threads and built in a `static`:
+/// ```
+/// # use slab_alloc::Pool;
+/// static PACKETS: Pool<[u8; 64], 8> = Pool::new();
+///
+/// let packet = PACKETS.alloc([0; 64]).unwrap();
+/// # assert_eq!(PACKETS.free_slots(), 7);
+/// ```
+pub struct Pool<T, const N: usize> {
+    slots: Section,
+    storage: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+// Objects are only reached through the slot claimed in the section, so each is used by one
+// thread at a time
+unsafe impl<T: Send, const N: usize> Sync for Pool<T, N> {}
+
+impl<T, const N: usize> Pool<T, N> {
+    /// An empty pool. Panics if `N` is not a slot count a section can have
+    pub const fn new() -> Self {
+        Self {
+            slots: Section::empty(mem::size_of::<T>(), N as u32),
+            storage: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+        }
+    }
+
+    /// Moves `value` into a free slot, or hands it back if every slot is taken
+    pub fn alloc(&self, value: T) -> Result<PoolBox<'_, T, N>, T> {
+        let Ok(slot) = self.slots.allocate() else {
+            return Err(value);
+        };
+        let object = self.storage[slot as usize].get();
+        // SAFETY: the slot was just claimed so nothing else uses it
+        unsafe { (*object).write(value) };
+        Ok(PoolBox {
+            pool: self,
+            slot,
+            _object: PhantomData,
+        })
+    }
+
+    /// The number of slots not holding an object
+    pub fn free_slots(&self) -> u32 {
+        self.slots.free_slots()
+    }
+}
+
+impl<T, const N: usize> Default for Pool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An object in a [`Pool`], dropped and its slot returned when the box is dropped. With the
+/// `embedded-dma` feature, boxes of words or arrays of them are DMA read and write buffers.
+/// A box is only shared between threads if its object can be:
+/// ```compile_fail
+/// # use slab_alloc::PoolBox;
+/// fn shared<T: Sync>() {}
+/// shared::<PoolBox<'static, core::cell::Cell<u32>, 8>>();
+/// ```
+pub struct PoolBox<'p, T, const N: usize> {
+    pool: &'p Pool<T, N>,
+    slot: u32,
+    _object: PhantomData<T>,
+}
+
+// Sharing the box only shares the object, the pool is reached through it only when it is dropped
+unsafe impl<T: Sync, const N: usize> Sync for PoolBox<'_, T, N> {}
+
+impl<T, const N: usize> PoolBox<'_, T, N> {
+    fn object(&self) -> *mut MaybeUninit<T> {
+        self.pool.storage[self.slot as usize].get()
+    }
+}
+
+impl<T, const N: usize> ops::Deref for PoolBox<'_, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the object is initialised and exclusively held until the box is dropped
+        unsafe { (*self.object()).assume_init_ref() }
+    }
+}
+
+impl<T, const N: usize> ops::DerefMut for PoolBox<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the object is initialised and exclusively held until the box is dropped
+        unsafe { (*self.object()).assume_init_mut() }
+    }
+}
+
+impl<T, const N: usize> Drop for PoolBox<'_, T, N> {
+    fn drop(&mut self) {
+        // SAFETY: the object is initialised and no longer borrowed
+        unsafe { (*self.object()).assume_init_drop() };
+        self.pool
+            .slots
+            .deallocate(self.slot)
+            .expect("Could not return object to pool");
+    }
+}
+
+// The object stays in the pool's storage, so its location is stable for as long as the box lives
+#[cfg(feature = "embedded-dma")]
+unsafe impl<T: embedded_dma::ReadTarget, const N: usize> embedded_dma::ReadBuffer
+    for PoolBox<'_, T, N>
+{
+    type Word = T::Word;
+
+    unsafe fn read_buffer(&self) -> (*const T::Word, usize) {
+        (**self).as_read_buffer()
+    }
+}
+
+#[cfg(feature = "embedded-dma")]
+unsafe impl<T: embedded_dma::WriteTarget, const N: usize> embedded_dma::WriteBuffer
+    for PoolBox<'_, T, N>
+{
+    type Word = T::Word;
+
+    unsafe fn write_buffer(&mut self) -> (*mut T::Word, usize) {
+        (**self).as_write_buffer()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted(u32);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn pool() {
+        let pool = Pool::<Counted, 8>::new();
+        let mut objects: [_; 8] = core::array::from_fn(|i| pool.alloc(Counted(i as u32)).ok());
+        assert!(pool.alloc(Counted(8)).is_err());
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+
+        let mut object = objects[3].take().unwrap();
+        object.0 += 10;
+        assert_eq!(object.0, 13);
+        drop(object);
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+        assert_eq!(pool.free_slots(), 1);
+        assert_eq!(pool.alloc(Counted(3)).ok().unwrap().0, 3);
+    }
+
+    #[cfg(feature = "embedded-dma")]
+    #[test]
+    fn dma_buffer() {
+        use embedded_dma::{ReadBuffer, WriteBuffer};
+        static PACKETS: Pool<[u16; 32], 8> = Pool::new();
+
+        let mut packet = PACKETS.alloc([0; 32]).unwrap();
+        let (write, len) = unsafe { packet.write_buffer() };
+        assert_eq!(len, 32);
+        assert_eq!(unsafe { packet.read_buffer() }, (write as *const u16, 32));
+        assert_eq!(write.cast::<[u16; 32]>(), &mut *packet as *mut _);
+    }
+}
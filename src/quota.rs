@@ -0,0 +1,166 @@
+use crate::api::{AllocError, Allocator};
+use crate::SlabAllocator;
+use core::alloc;
+use core::ptr;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// Allocator wrapping a [`SlabAllocator`] that tags every allocation with its owner's tag and
+/// fails any that would take the owner past its budget of bytes or slots, even if the allocator
+/// has space, so a noisy subsystem cannot starve the others. Only allocations made through the
+/// quota count against it
+pub struct Quota<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    tag: u16,
+    max_bytes: usize,
+    max_slots: u32,
+    bytes: AtomicUsize,
+    slots: AtomicU32,
+}
+
+impl<'a, 'm, const N: usize> Quota<'a, 'm, N> {
+    /// Wraps `allocator` for the owner of `tag`, allowing it at most `max_bytes` of slabs and
+    /// `max_slots` slots
+    pub fn new(
+        allocator: &'a SlabAllocator<'m, N>,
+        tag: u16,
+        max_bytes: usize,
+        max_slots: u32,
+    ) -> Self {
+        Self {
+            allocator,
+            tag,
+            max_bytes,
+            max_slots,
+            bytes: AtomicUsize::new(0),
+            slots: AtomicU32::new(0),
+        }
+    }
+
+    /// The bytes of the slabs currently allocated through the quota
+    pub fn bytes_used(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// The slots currently allocated through the quota
+    pub fn slots_used(&self) -> u32 {
+        self.slots.load(Ordering::Relaxed)
+    }
+
+    /// The bytes and slots taken by the slab at `ptr`
+    fn cost(&self, ptr: ptr::NonNull<u8>) -> Option<(usize, u32)> {
+        let (_, section, slot) = self.allocator.locate(ptr)?;
+        let slots = section.run_of(slot).count_ones();
+        Some((section.size * slots as usize, slots))
+    }
+
+    /// Charges `bytes` and `slots` to the quota if they fit in its budget
+    fn charge(&self, bytes: usize, slots: u32) -> bool {
+        if self
+            .bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes)
+                    .filter(|&used| used <= self.max_bytes)
+            })
+            .is_err()
+        {
+            return false;
+        }
+        if self
+            .slots
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(slots)
+                    .filter(|&used| used <= self.max_slots)
+            })
+            .is_err()
+        {
+            self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// Gives `bytes` and `slots` back to the quota
+    fn refund(&self, bytes: usize, slots: u32) {
+        self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+        self.slots.fetch_sub(slots, Ordering::Relaxed);
+    }
+}
+
+unsafe impl<'a, 'm, const N: usize> Allocator for Quota<'a, 'm, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations take no slab and so cost nothing
+        if layout.size() == 0 {
+            return Ok(self.allocator.allocate_tagged(layout, self.tag)?);
+        }
+        // Charge the slab of the section the allocation would be served from before taking it,
+        // so a request over budget never claims a slab
+        let (bytes, slots) = self
+            .allocator
+            .section_for_layout(layout)
+            .map_or((0, 0), |index| (self.allocator.blocks[index].size, 1));
+        if !self.charge(bytes, slots) {
+            return Err(AllocError);
+        }
+        let slab = match self.allocator.allocate_tagged(layout, self.tag) {
+            Ok(slab) => slab,
+            Err(error) => {
+                self.refund(bytes, slots);
+                return Err(error.into());
+            }
+        };
+        // A fuller section may have served it from a larger slab
+        let (cost_bytes, cost_slots) = self.cost(slab.cast()).unwrap_or((bytes, slots));
+        self.refund(bytes, slots);
+        if !self.charge(cost_bytes, cost_slots) {
+            // SAFETY: the slab was just allocated with this layout and never handed out
+            unsafe { self.allocator.deallocate(slab.cast(), layout) };
+            return Err(AllocError);
+        }
+        Ok(slab)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        if layout.size() != 0 {
+            if let Some((bytes, slots)) = self.cost(ptr) {
+                self.refund(bytes, slots);
+            }
+        }
+        self.allocator.deallocate(ptr, layout);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn quota() {
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let noisy = Quota::new(&allocator, 1, 16, 8);
+        let critical = Quota::new(&allocator, 2, 64, 1);
+
+        let first = noisy.allocate(layout).unwrap();
+        noisy.allocate(layout).unwrap();
+        assert!(noisy.allocate(layout).is_err());
+        assert_eq!((noisy.bytes_used(), noisy.slots_used()), (16, 2));
+        assert_eq!(allocator.bytes_used_by(1), 16);
+
+        critical.allocate(layout).unwrap();
+        assert!(critical.allocate(layout).is_err());
+        // The heap still has space for others
+        assert_eq!(allocator.gauges()[0].get(), 3);
+        // Requests over budget are refused without taking a slab
+        #[cfg(feature = "stats")]
+        assert_eq!(allocator.stats().allocations, 3);
+
+        unsafe { noisy.deallocate(first.cast(), layout) };
+        assert_eq!(noisy.bytes_used(), 8);
+        noisy.allocate(layout).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use crate::api::Allocator;
+use crate::error::SlabError;
+use crate::SlabAllocator;
+use core::{alloc, mem, ptr};
+
+/// A slab claimed by [`reserve`](SlabAllocator::reserve) that is freed again unless
+/// [`commit`](Reservation::commit)ted, so a multi-part operation can back out of its allocations
+/// when a later step fails
+#[must_use = "a reservation is freed when dropped"]
+pub struct Reservation<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    slab: ptr::NonNull<[u8]>,
+    layout: alloc::Layout,
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Tentatively allocates a slab for `layout`, freed when the returned reservation is
+    /// cancelled or dropped without being committed
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn reserve(&self, layout: alloc::Layout) -> Result<Reservation<'_, 'm, N>, SlabError> {
+        Ok(Reservation {
+            allocator: self,
+            slab: self.try_allocate(layout)?,
+            layout,
+        })
+    }
+}
+
+impl<const N: usize> Reservation<'_, '_, N> {
+    /// The reserved slab, which may be written before the reservation is committed
+    pub fn slab(&self) -> ptr::NonNull<[u8]> {
+        self.slab
+    }
+
+    /// Keeps the slab allocated, returning it to be freed like any other allocation
+    pub fn commit(self) -> ptr::NonNull<[u8]> {
+        let slab = self.slab;
+        mem::forget(self);
+        slab
+    }
+
+    /// Frees the slab, as dropping the reservation does
+    pub fn cancel(self) {}
+}
+
+impl<const N: usize> Drop for Reservation<'_, '_, N> {
+    fn drop(&mut self) {
+        // SAFETY: the slab was allocated with this layout and, not being committed, is not used
+        // after the reservation
+        unsafe { self.allocator.deallocate(self.slab.cast(), self.layout) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn reserve() {
+        let mut buf = Aligned([0u8; 8 * 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+
+        let header = allocator.reserve(layout).unwrap();
+        let body = allocator.reserve(layout).unwrap();
+        assert_eq!(allocator.gauges()[0].get(), 2);
+        // A later step fails, backing out of both
+        body.cancel();
+        drop(header);
+        assert_eq!(allocator.gauges()[0].get(), 0);
+
+        let slab = allocator.reserve(layout).unwrap().commit();
+        assert_eq!(allocator.gauges()[0].get(), 1);
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+    }
+}
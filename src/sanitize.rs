@@ -0,0 +1,181 @@
+//! Annotations telling Valgrind and AddressSanitizer which parts of the buffer are allocated.
+//! Without them the whole buffer looks like valid memory, hiding use-after-free and overflows.
+//! Every function is a no-op unless the `sanitize` feature is enabled
+use core::ptr;
+
+#[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+mod valgrind {
+    pub(super) const CREATE_MEMPOOL: usize = 0x1303;
+    pub(super) const DESTROY_MEMPOOL: usize = 0x1304;
+    pub(super) const MEMPOOL_ALLOC: usize = 0x1305;
+    pub(super) const MEMPOOL_FREE: usize = 0x1306;
+    pub(super) const MAKE_MEM_NOACCESS: usize = 0x4d43_0000;
+    pub(super) const MAKE_MEM_DEFINED: usize = 0x4d43_0002;
+
+    /// Issues a client request, which does nothing when not running under Valgrind
+    pub(super) fn request(args: [usize; 6]) {
+        // SAFETY: the magic sequence rotates rdi by a multiple of 64 and exchanges rbx with
+        // itself, so no register is changed except rdx, the result
+        unsafe {
+            core::arch::asm!(
+                "rol rdi, 3",
+                "rol rdi, 13",
+                "rol rdi, 61",
+                "rol rdi, 51",
+                "xchg rbx, rbx",
+                in("rax") args.as_ptr(),
+                inout("rdx") 0usize => _,
+            );
+        }
+    }
+}
+
+// `cfg(sanitize)` needs a nightly compiler, so it is only evaluated with the feature enabled
+#[cfg(feature = "sanitize")]
+mod asan {
+    use core::ptr;
+
+    #[cfg(sanitize = "address")]
+    extern "C" {
+        fn __asan_poison_memory_region(addr: *const u8, size: usize);
+        fn __asan_unpoison_memory_region(addr: *const u8, size: usize);
+    }
+
+    /// Reports accesses to `memory` as errors, if running under AddressSanitizer
+    #[inline]
+    pub(super) fn poison(_memory: ptr::NonNull<[u8]>) {
+        #[cfg(sanitize = "address")]
+        unsafe {
+            __asan_poison_memory_region(_memory.cast::<u8>().as_ptr(), _memory.len())
+        };
+    }
+
+    /// Allows accesses to `memory` again, if running under AddressSanitizer
+    #[inline]
+    pub(super) fn unpoison(_memory: ptr::NonNull<[u8]>) {
+        #[cfg(sanitize = "address")]
+        unsafe {
+            __asan_unpoison_memory_region(_memory.cast::<u8>().as_ptr(), _memory.len())
+        };
+    }
+}
+
+/// The buffer `pool` has been handed to an allocator, none of it is allocated
+#[inline]
+pub(crate) fn created(_pool: ptr::NonNull<[u8]>) {
+    #[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+    valgrind::request([
+        valgrind::CREATE_MEMPOOL,
+        _pool.cast::<u8>().as_ptr() as usize,
+        0,
+        0,
+        0,
+        0,
+    ]);
+    poison(_pool);
+}
+
+/// The allocator owning `pool` is gone, the buffer is the user's again
+#[cfg(feature = "sanitize")]
+#[inline]
+pub(crate) fn destroyed(_pool: ptr::NonNull<[u8]>) {
+    #[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+    valgrind::request([
+        valgrind::DESTROY_MEMPOOL,
+        _pool.cast::<u8>().as_ptr() as usize,
+        0,
+        0,
+        0,
+        0,
+    ]);
+    unpoison(_pool);
+}
+
+/// `slab` of `pool` has been handed out
+#[inline]
+pub(crate) fn allocated(_pool: ptr::NonNull<[u8]>, _slab: ptr::NonNull<[u8]>) {
+    #[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+    valgrind::request([
+        valgrind::MEMPOOL_ALLOC,
+        _pool.cast::<u8>().as_ptr() as usize,
+        _slab.cast::<u8>().as_ptr() as usize,
+        _slab.len(),
+        0,
+        0,
+    ]);
+    #[cfg(feature = "sanitize")]
+    asan::unpoison(_slab);
+}
+
+/// `slab` of `pool` has been returned
+#[inline]
+pub(crate) fn freed(_pool: ptr::NonNull<[u8]>, _slab: ptr::NonNull<[u8]>) {
+    #[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+    valgrind::request([
+        valgrind::MEMPOOL_FREE,
+        _pool.cast::<u8>().as_ptr() as usize,
+        _slab.cast::<u8>().as_ptr() as usize,
+        0,
+        0,
+        0,
+    ]);
+    #[cfg(feature = "sanitize")]
+    asan::poison(_slab);
+}
+
+/// Marks `memory` as inaccessible
+#[inline]
+pub(crate) fn poison(_memory: ptr::NonNull<[u8]>) {
+    #[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+    valgrind::request([
+        valgrind::MAKE_MEM_NOACCESS,
+        _memory.cast::<u8>().as_ptr() as usize,
+        _memory.len(),
+        0,
+        0,
+        0,
+    ]);
+    #[cfg(feature = "sanitize")]
+    asan::poison(_memory);
+}
+
+/// Marks `memory` as accessible, for the allocator's own accesses to memory that is not allocated
+#[inline]
+pub(crate) fn unpoison(_memory: ptr::NonNull<[u8]>) {
+    #[cfg(all(feature = "sanitize", target_arch = "x86_64"))]
+    valgrind::request([
+        valgrind::MAKE_MEM_DEFINED,
+        _memory.cast::<u8>().as_ptr() as usize,
+        _memory.len(),
+        0,
+        0,
+        0,
+    ]);
+    #[cfg(feature = "sanitize")]
+    asan::unpoison(_memory);
+}
+
+#[cfg(all(test, feature = "sanitize"))]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn annotated() {
+        let mut buf = [0u8; 64];
+        {
+            let allocator =
+                SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf[..]).unwrap();
+            let layout = Layout::new::<u64>();
+            let slab = allocator.allocate(layout).unwrap();
+            unsafe {
+                slab.cast::<u8>().write(1);
+                allocator.deallocate(slab.cast(), layout);
+            }
+            allocator.scrub_step(8);
+        }
+        buf[0] = 2;
+        assert_eq!(buf[0], 2);
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(feature = "async")]
+use crate::future;
+use crate::{sanitize, SlabAllocator};
+use core::sync::atomic::Ordering;
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Reads and rewrites up to `budget` free slots, resuming after the last slot visited by the
+    /// previous call, so memory ECC corrects latent bit errors in idle slabs.
+    /// Each free slot is held while it is rewritten so it cannot be allocated concurrently, without
+    /// counting towards the slots in use or their peak.
+    /// Returns the number of slots scrubbed
+    pub fn scrub_step(&self, budget: usize) -> usize {
+        let total: usize = self
+            .blocks
+            .iter()
+            .map(|section| section.total_slots() as usize)
+            .sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let mut position = self.scrub_cursor.load(Ordering::Relaxed) % total;
+        let mut scrubbed = 0;
+        for _ in 0..budget.min(total) {
+            // Find the section and slot at the cursor
+            let mut slot = position;
+            let section = self
+                .blocks
+                .iter()
+                .find(|section| {
+                    let slots = section.total_slots() as usize;
+                    if slot < slots {
+                        true
+                    } else {
+                        slot -= slots;
+                        false
+                    }
+                })
+                .expect("Scrub cursor out of range");
+            position = (position + 1) % total;
+
+            let scrub = || {
+                let slab = section.slot_ptr(slot as u32);
+                sanitize::unpoison(slab);
+                let slab = slab.cast::<u8>();
+                for i in 0..section.size {
+                    // SAFETY: the slot is held free so nothing else accesses it
+                    unsafe {
+                        let byte = slab.add(i);
+                        byte.write_volatile(byte.read_volatile());
+                    }
+                }
+                sanitize::poison(section.slot_ptr(slot as u32));
+            };
+            if !section.with_free(slot as u32, scrub) {
+                continue;
+            }
+            // An allocation may have been refused while the slot was held
+            #[cfg(feature = "async")]
+            future::wake(section);
+            scrubbed += 1;
+        }
+        self.scrub_cursor.store(position, Ordering::Relaxed);
+        scrubbed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn scrub_step() {
+        let mut buf = Aligned([0u8; 64]);
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicU8::new(0)),
+                Section::new(8, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+
+        let slab = allocator.allocate(Layout::new::<u32>()).unwrap();
+        unsafe { slab.cast::<u32>().write_unaligned(0x1234_5678) };
+
+        // 9 slots, one of them allocated
+        assert_eq!(allocator.scrub_step(4), 3);
+        assert_eq!(allocator.scrub_step(4), 4);
+        assert_eq!(allocator.scrub_step(4), 3);
+        assert_eq!(allocator.scrub_step(100), 8);
+
+        assert_eq!(allocator.blocks[0].free_slots(), 7);
+        assert_eq!(allocator.blocks[1].free_slots(), 1);
+        // Holding free slots while scrubbing them does not count them as used
+        assert_eq!(
+            allocator.blocks.each_ref().map(|section| section.peak()),
+            [1, 0]
+        );
+        assert_eq!(unsafe { slab.cast::<u32>().read_unaligned() }, 0x1234_5678);
+    }
+}
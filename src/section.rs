@@ -1,8 +1,112 @@
+use crate::api::AllocError;
+use crate::atomic::{self, Ordering};
+use crate::freelist::FreeList;
 use core::alloc;
-use core::sync::atomic::{self, Ordering};
+use core::fmt;
+use core::mem;
+use core::ptr;
 
 /// Result type for allocation errors
-pub type Result<T> = core::result::Result<T, alloc::AllocError>;
+pub type Result<T> = core::result::Result<T, AllocError>;
+
+/// The pattern freed slabs are filled with when the `poison` feature is enabled
+pub const POISON: u32 = 0xDEAD_BEEF;
+
+/// A bitmap with a bit for each slot of the largest section
+#[cfg(not(feature = "u128"))]
+pub(crate) type Bitmap = u64;
+/// A bitmap with a bit for each slot of the largest section
+#[cfg(feature = "u128")]
+pub(crate) type Bitmap = u128;
+/// An atomic [`Bitmap`]
+#[cfg(not(feature = "u128"))]
+pub(crate) type AtomicBitmap = atomic::AtomicU64;
+/// An atomic [`Bitmap`]
+#[cfg(feature = "u128")]
+pub(crate) type AtomicBitmap = portable_atomic::AtomicU128;
+
+/// The message given for a slot count no section can have
+#[cfg(not(feature = "u128"))]
+pub(crate) const INVALID_SLOT_COUNT: &str = "Slot counts must be one of 1, 8, 16, 32 or 64";
+/// The message given for a slot count no section can have
+#[cfg(feature = "u128")]
+pub(crate) const INVALID_SLOT_COUNT: &str = "Slot counts must be one of 1, 8, 16, 32, 64 or 128";
+
+/// The largest number of slots a section can have
+pub(crate) const MAX_SLOTS: usize = Bitmap::BITS as usize;
+
+/// The fewest slots a section can have that is at least `count`.
+/// Panics if `count` is more than a section can hold
+pub(crate) const fn slots_for(count: usize) -> u32 {
+    assert!(count <= MAX_SLOTS, "Too many slots for one section");
+    match count {
+        0..=1 => 1,
+        2..=8 => 8,
+        9..=16 => 16,
+        17..=32 => 32,
+        33..=64 => 64,
+        _ => 128,
+    }
+}
+
+/// Bookkeeping recorded for each slot by the optional debugging features
+#[cfg(any(feature = "amp", feature = "leak-check", feature = "tags"))]
+pub(crate) struct SlotMeta {
+    /// The core that allocated the slot
+    #[cfg(feature = "amp")]
+    pub(crate) owner: atomic::AtomicU8,
+    /// Where the slot was allocated, or null if unknown
+    #[cfg(feature = "leak-check")]
+    pub(crate) location: atomic::AtomicPtr<core::panic::Location<'static>>,
+    /// The tag the slot was allocated with
+    #[cfg(feature = "tags")]
+    pub(crate) tag: atomic::AtomicU16,
+}
+
+#[cfg(any(feature = "amp", feature = "leak-check", feature = "tags"))]
+impl SlotMeta {
+    const fn new() -> Self {
+        Self {
+            #[cfg(feature = "amp")]
+            owner: atomic::AtomicU8::new(0),
+            #[cfg(feature = "leak-check")]
+            location: atomic::AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "tags")]
+            tag: atomic::AtomicU16::new(0),
+        }
+    }
+}
+
+/// Where a live allocation is and how large its slab is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SlotInfo {
+    /// The index of the section the slab belongs to
+    pub section: usize,
+    /// The index of the slot within the section
+    pub slot: u32,
+    /// The size of the slab
+    pub size: usize,
+    /// The alignment the section guarantees
+    pub align: usize,
+    /// The core that owns the allocation
+    #[cfg(feature = "amp")]
+    pub owner: u8,
+    /// The tag the allocation was made with
+    #[cfg(feature = "tags")]
+    pub tag: u16,
+}
+
+/// Live count of a section's allocated slots, readable with a single relaxed load
+#[derive(Debug)]
+pub struct Gauge(atomic::AtomicU32);
+
+impl Gauge {
+    /// The number of slots allocated
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
 
 /// Possible sizes of sections
 pub enum Atomics {
@@ -16,6 +120,123 @@ pub enum Atomics {
     U32(atomic::AtomicU32),
     /// 64 blocks
     U64(atomic::AtomicU64),
+    /// 128 blocks
+    #[cfg(feature = "u128")]
+    U128(portable_atomic::AtomicU128),
+}
+
+impl Atomics {
+    /// The representation tracking exactly `slots` slots, if there is one
+    pub(crate) const fn with_slots(slots: u32) -> Option<Self> {
+        match slots {
+            1 => Some(Atomics::Bool(atomic::AtomicBool::new(false))),
+            8 => Some(Atomics::U8(atomic::AtomicU8::new(0))),
+            16 => Some(Atomics::U16(atomic::AtomicU16::new(0))),
+            32 => Some(Atomics::U32(atomic::AtomicU32::new(0))),
+            64 => Some(Atomics::U64(atomic::AtomicU64::new(0))),
+            #[cfg(feature = "u128")]
+            128 => Some(Atomics::U128(portable_atomic::AtomicU128::new(0))),
+            _ => None,
+        }
+    }
+
+    /// The bits set, widened to a bitmap
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::useless_conversion)]
+    fn load_bits(&self) -> Bitmap {
+        match self {
+            Atomics::Bool(b) => Bitmap::from(b.load(Ordering::Acquire)),
+            Atomics::U8(u) => Bitmap::from(u.load(Ordering::Acquire)),
+            Atomics::U16(u) => Bitmap::from(u.load(Ordering::Acquire)),
+            Atomics::U32(u) => Bitmap::from(u.load(Ordering::Acquire)),
+            Atomics::U64(u) => Bitmap::from(u.load(Ordering::Acquire)),
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.load(Ordering::Acquire),
+        }
+    }
+
+    /// Replaces the bits with those `f` derives from them, in a critical section
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[cfg(feature = "critical-section")]
+    #[allow(clippy::unnecessary_cast)]
+    fn modify_bits(&self, f: impl FnOnce(Bitmap) -> Bitmap) {
+        critical_section::with(|_| {
+            let bits = f(self.load_bits());
+            match self {
+                Atomics::Bool(b) => b.store(bits & 1 != 0, Ordering::Release),
+                Atomics::U8(u) => u.store(bits as u8, Ordering::Release),
+                Atomics::U16(u) => u.store(bits as u16, Ordering::Release),
+                Atomics::U32(u) => u.store(bits as u32, Ordering::Release),
+                Atomics::U64(u) => u.store(bits as u64, Ordering::Release),
+                #[cfg(feature = "u128")]
+                Atomics::U128(u) => u.store(bits, Ordering::Release),
+            }
+        });
+    }
+
+    /// Sets the bits of `mask`
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast)]
+    fn set_bits(&self, mask: Bitmap) {
+        #[cfg(feature = "critical-section")]
+        self.modify_bits(|bits| bits | mask);
+        #[cfg(not(feature = "critical-section"))]
+        match self {
+            Atomics::Bool(b) => {
+                if mask & 1 != 0 {
+                    b.store(true, Ordering::Release)
+                }
+            }
+            Atomics::U8(u) => {
+                u.fetch_or(mask as u8, Ordering::Release);
+            }
+            Atomics::U16(u) => {
+                u.fetch_or(mask as u16, Ordering::Release);
+            }
+            Atomics::U32(u) => {
+                u.fetch_or(mask as u32, Ordering::Release);
+            }
+            Atomics::U64(u) => {
+                u.fetch_or(mask as u64, Ordering::Release);
+            }
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => {
+                u.fetch_or(mask, Ordering::Release);
+            }
+        }
+    }
+
+    /// Clears the bits of `mask`
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast)]
+    fn clear_bits(&self, mask: Bitmap) {
+        #[cfg(feature = "critical-section")]
+        self.modify_bits(|bits| bits & !mask);
+        #[cfg(not(feature = "critical-section"))]
+        match self {
+            Atomics::Bool(b) => {
+                if mask & 1 != 0 {
+                    b.store(false, Ordering::Release)
+                }
+            }
+            Atomics::U8(u) => {
+                u.fetch_and(!(mask as u8), Ordering::Release);
+            }
+            Atomics::U16(u) => {
+                u.fetch_and(!(mask as u16), Ordering::Release);
+            }
+            Atomics::U32(u) => {
+                u.fetch_and(!(mask as u32), Ordering::Release);
+            }
+            Atomics::U64(u) => {
+                u.fetch_and(!(mask as u64), Ordering::Release);
+            }
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => {
+                u.fetch_and(!mask, Ordering::Release);
+            }
+        }
+    }
 }
 
 macro_rules! from_atomic {
@@ -40,143 +261,952 @@ from_atomic! {
     > for Atomics;
 }
 
+// Sections can still be given core's atomics
+#[cfg(feature = "portable-atomic")]
+macro_rules! from_core_atomic {
+    ($(($atomic:ident, $variant:path)),+) => {
+        $(
+            impl From<core::sync::atomic::$atomic> for Atomics {
+                fn from(t: core::sync::atomic::$atomic) -> Self {
+                    $variant(atomic::$atomic::new(t.into_inner()))
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "portable-atomic")]
+from_core_atomic! {
+    (AtomicBool, Atomics::Bool),
+    (AtomicU8, Atomics::U8),
+    (AtomicU16, Atomics::U16),
+    (AtomicU32, Atomics::U32),
+    (AtomicU64, Atomics::U64)
+}
+
+#[cfg(feature = "u128")]
+from_atomic! {
+    impl From<(portable_atomic::AtomicU128, Atomics::U128)> for Atomics;
+}
+
 /// A struct that describes how large slabs should be and the quantity
 pub struct Section {
     /// The size of the slabs
     pub size: usize,
+    pub(crate) align: usize,
+    pub(crate) boundary: usize,
+    pub(crate) color: usize,
+    pub(crate) zeroed: bool,
+    pub(crate) watermark: u32,
+    pub(crate) low: atomic::AtomicBool,
+    #[cfg(feature = "zeroize")]
+    pub(crate) sensitive: bool,
     pub(crate) allocated: Atomics,
+    pub(crate) runs: Option<Atomics>,
+    pub(crate) free_list: Option<FreeList>,
+    pub(crate) used: Gauge,
+    pub(crate) peak: atomic::AtomicU32,
+    pub(crate) region: ptr::NonNull<u8>,
+    pub(crate) next: atomic::AtomicPtr<Section>,
+    /// The index of a section added to a live allocator, kept while it stays added
+    pub(crate) index: usize,
+    pub(crate) grown: bool,
+    #[cfg(any(feature = "amp", feature = "leak-check", feature = "tags"))]
+    pub(crate) meta: [SlotMeta; MAX_SLOTS],
+    #[cfg(feature = "async")]
+    pub(crate) waiters: [crate::future::Waiter; crate::future::WAITERS],
+    #[cfg(feature = "stats")]
+    pub(crate) counters: crate::stats::Counters,
+    #[cfg(feature = "stats")]
+    pub(crate) requests: crate::stats::Requests,
 }
 
+// The region pointer is only dereferenced through slots claimed in `allocated`, which is only
+// ever modified atomically
+unsafe impl Send for Section {}
+unsafe impl Sync for Section {}
+
 impl Section {
     /// Constructor of section
     pub fn new<A: Into<Atomics>>(size: usize, quantity: A) -> Self {
+        let section = Self {
+            allocated: quantity.into(),
+            ..Self::empty(size, 1)
+        };
+        // Slots may start out allocated
+        let used = section.total_slots() - section.free_slots();
+        section.used.0.store(used, Ordering::Relaxed);
+        section.peak.store(used, Ordering::Relaxed);
+        section
+    }
+
+    /// A section of at least `slots` free slabs of `size` bytes, rounding `slots` up to the
+    /// fewest a section can have (1, 8, 16, 32, 64 or, with the `u128` feature, 128).
+    /// Panics if `slots` is more than that
+    pub const fn with_capacity(size: usize, slots: usize) -> Self {
+        Self::empty(size, slots_for(slots))
+    }
+
+    /// A section of `slots` free slabs of `size` bytes (`slots` being one of 1, 8, 16, 32, 64 or,
+    /// with the `u128` feature, 128), usable in constant expressions
+    pub const fn empty(size: usize, slots: u32) -> Self {
+        let Some(allocated) = Atomics::with_slots(slots) else {
+            panic!("{}", INVALID_SLOT_COUNT);
+        };
         Self {
             size,
-            allocated: quantity.into(),
+            align: 1,
+            boundary: 0,
+            color: 0,
+            zeroed: false,
+            watermark: 0,
+            low: atomic::AtomicBool::new(false),
+            #[cfg(feature = "zeroize")]
+            sensitive: false,
+            allocated,
+            runs: None,
+            free_list: None,
+            used: Gauge(atomic::AtomicU32::new(0)),
+            peak: atomic::AtomicU32::new(0),
+            region: ptr::NonNull::dangling(),
+            next: atomic::AtomicPtr::new(ptr::null_mut()),
+            index: 0,
+            grown: false,
+            #[cfg(any(feature = "amp", feature = "leak-check", feature = "tags"))]
+            meta: [const { SlotMeta::new() }; MAX_SLOTS],
+            #[cfg(feature = "async")]
+            waiters: [const { crate::future::Waiter::new() }; crate::future::WAITERS],
+            #[cfg(feature = "stats")]
+            counters: crate::stats::Counters::new(),
+            #[cfg(feature = "stats")]
+            requests: crate::stats::Requests::new(),
+        }
+    }
+
+    /// Keeps every slab from crossing a multiple of `boundary` bytes (a power of two), as some
+    /// DMA controllers require. Slabs are packed into `boundary` sized windows, leaving a gap
+    /// at the end of each window if `size` does not divide it
+    pub const fn with_boundary(self, boundary: usize) -> Self {
+        assert!(
+            boundary.is_power_of_two(),
+            "Boundary must be a power of two"
+        );
+        assert!(
+            self.size <= boundary,
+            "Slabs larger than the boundary would always cross it"
+        );
+        Self { boundary, ..self }
+    }
+
+    /// Places the section's slabs on multiples of `align` bytes (a power of two), as DMA
+    /// descriptors and SIMD types need, rounding `size` up to a multiple of it
+    pub const fn with_align(self, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "Alignment must be a power of two");
+        Self {
+            size: self.size.next_multiple_of(align),
+            align,
+            ..self
+        }
+    }
+
+    /// Colors the section's slabs by following each with `step` unused bytes, so successive
+    /// slabs start `step` bytes further along a cache line or set than a tight packing would put
+    /// them, rather than all landing on the same ones
+    pub const fn with_coloring(self, step: usize) -> Self {
+        Self {
+            color: step,
+            ..self
+        }
+    }
+
+    /// Keeps the section's free slabs zeroed by clearing them when they are freed, so zeroed
+    /// allocations from it need no work
+    pub const fn pre_zeroed(self) -> Self {
+        assert!(
+            self.free_list.is_none(),
+            "Free list sections keep their links in free slabs"
+        );
+        Self {
+            zeroed: true,
+            ..self
+        }
+    }
+
+    /// Flags the section as low once an allocation leaves fewer than `slots` slots free,
+    /// reporting an [`Event::LowMemory`](crate::Event::LowMemory) to the allocator's observer,
+    /// until frees bring it back to `slots`, so the application can shed load before
+    /// allocations start failing
+    pub const fn with_watermark(self, slots: u32) -> Self {
+        Self {
+            watermark: slots,
+            ..self
+        }
+    }
+
+    /// Marks the section as holding secrets such as keys and credentials, so its slabs are wiped
+    /// with volatile writes when they are freed, before they can be handed out again
+    #[cfg(feature = "zeroize")]
+    pub const fn sensitive(self) -> Self {
+        Self {
+            sensitive: true,
+            ..self
         }
     }
 
+    /// Lets an allocation too large for any slab span a run of adjacent free slots of the
+    /// section, so one section serves a range of sizes rather than them failing.
+    /// Panics if the slabs are not back to back, as with a boundary or coloring
+    pub const fn spanning(self) -> Self {
+        assert!(
+            self.boundary == 0 && self.color == 0,
+            "Only sections of adjacent slabs can span slots"
+        );
+        assert!(
+            self.free_list.is_none(),
+            "Free list sections cannot span slots"
+        );
+        let Some(runs) = Atomics::with_slots(self.total_slots()) else {
+            panic!("Slot count of a section");
+        };
+        Self {
+            runs: Some(runs),
+            ..self
+        }
+    }
+
+    /// Tracks the section's free slots in a list threaded through the free slabs themselves, a
+    /// Treiber stack of slot indices, so an allocation pops a slot rather than scanning the
+    /// bitmap, which the section still keeps to detect double frees and report usage. Slabs are
+    /// aligned for and hold the `u32` linking each free slab to the next, and particular slots
+    /// cannot be claimed, so [`allocate_at`](crate::SlabAllocator::allocate_at) fails on the
+    /// section and compaction, scrubbing and warming up leave it alone.
+    /// Panics if the slabs are smaller than a `u32`, or if the section spans slots or is
+    /// pre-zeroed
+    pub const fn with_free_list(self) -> Self {
+        assert!(
+            self.size >= mem::size_of::<u32>(),
+            "Free list slabs must hold the u32 linking them"
+        );
+        assert!(self.runs.is_none(), "Free list sections cannot span slots");
+        assert!(
+            !self.zeroed,
+            "Free list sections keep their links in free slabs"
+        );
+        let section = if self.align < mem::align_of::<u32>() {
+            self.with_align(mem::align_of::<u32>())
+        } else {
+            self
+        };
+        Self {
+            free_list: Some(FreeList::new()),
+            ..section
+        }
+    }
+
+    /// An empty section with the same slabs as this one, yet to be placed
+    pub(crate) fn sibling(&self) -> Self {
+        Self {
+            runs: self
+                .runs
+                .as_ref()
+                .and_then(|_| Atomics::with_slots(self.total_slots())),
+            free_list: self.free_list.as_ref().map(|_| FreeList::new()),
+            align: self.align,
+            boundary: self.boundary,
+            color: self.color,
+            zeroed: self.zeroed,
+            watermark: self.watermark,
+            #[cfg(feature = "zeroize")]
+            sensitive: self.sensitive,
+            ..Section::new(
+                self.size,
+                Atomics::with_slots(self.total_slots()).expect("Slot count of a section"),
+            )
+        }
+    }
+
+    /// The alignment every slab of the section has, given where its region was placed
+    pub(crate) fn slot_align(&self) -> usize {
+        let region = 1 << (self.region.as_ptr() as usize).trailing_zeros();
+        if self.total_slots() == 1 || self.size == 0 {
+            region
+        } else {
+            // Slots are a multiple of the stride (or of the boundary) apart
+            region.min(1 << self.stride().trailing_zeros())
+        }
+    }
+
+    /// Whether the section's slabs can hold `layout`
+    pub(crate) fn fits(&self, layout: alloc::Layout) -> bool {
+        self.size >= layout.pad_to_align().size() && self.slot_align() >= layout.align()
+    }
+
+    /// The alignment the start of the section must be placed on
+    pub(crate) const fn placement(&self) -> usize {
+        if self.align > self.boundary {
+            self.align
+        } else {
+            self.boundary
+        }
+    }
+
+    /// The distance between the starts of successive slots, outside of boundary gaps
+    const fn stride(&self) -> usize {
+        self.size + self.color
+    }
+
+    /// The number of slots in each boundary window
+    const fn slots_per_window(&self) -> usize {
+        let stride = self.stride();
+        (self.boundary - self.size) / if stride == 0 { 1 } else { stride } + 1
+    }
+
+    /// The offset of slot `index` from the start of the region
+    const fn slot_offset(&self, index: u32) -> usize {
+        let index = index as usize;
+        if self.boundary == 0 {
+            index * self.stride()
+        } else {
+            let per_window = self.slots_per_window();
+            index / per_window * self.boundary + index % per_window * self.stride()
+        }
+    }
+
+    /// The number of bytes of buffer the section occupies
+    pub(crate) const fn capacity(&self) -> usize {
+        self.slot_offset(self.total_slots() - 1) + self.size
+    }
+
+    /// The memory of slot `index`
+    pub(crate) fn slot_ptr(&self, index: u32) -> ptr::NonNull<[u8]> {
+        // SAFETY: `index` is less than `total_slots` so the slot lies within the region
+        let start = unsafe { self.region.add(self.slot_offset(index)) };
+        ptr::NonNull::slice_from_raw_parts(start, self.size)
+    }
+
+    /// The index of the slot `ptr` points into, if it lies within this section
+    pub(crate) fn slot_of(&self, ptr: ptr::NonNull<u8>) -> Option<u32> {
+        let offset = (ptr.as_ptr() as usize).checked_sub(self.region.as_ptr() as usize)?;
+        if offset >= self.capacity() {
+            return None;
+        }
+        // Pointers into the color after a slab belong to no slot
+        let stride = self.stride();
+        if self.boundary == 0 {
+            return (offset % stride < self.size).then_some((offset / stride) as u32);
+        }
+        // Nor do pointers into the gap at the end of a window
+        let per_window = self.slots_per_window();
+        let within = offset % self.boundary;
+        if within / stride >= per_window || within % stride >= self.size {
+            return None;
+        }
+        Some((offset / self.boundary * per_window + within / stride) as u32)
+    }
+
+    /// Whether `ptr` points into one of the section's slabs, allocated or not
+    pub fn contains(&self, ptr: ptr::NonNull<u8>) -> bool {
+        self.slot_of(ptr).is_some()
+    }
+
     pub(crate) fn allocate(&self) -> Result<u32> {
+        if let Some(list) = &self.free_list {
+            return self.take_listed(list.pop(self, true));
+        }
         // Abstracted (don't want to copy it 4 times):
         //
-        //  // Acquire current value
-        //  let load = u.load(Ordering::Acquire);
-        //
-        //  // Check if there are any free slots
-        //  if !load == 0 {
-        //      Err(alloc::AllocError)
-        //  } else {
-        //
-        //      // Shamelessly stolen from: https://stackoverflow.com/questions/31393100/how-to-get-position-of-right-most-set-bit-in-c
-        //      let set_bit = !load & (load + 1);
-        //
-        //      // Set bit to be allocated (with paired release)
-        //      u.store(load | set_bit, Ordering::Release);
-        //
-        //      // Return index
-        //      Ok(set_bit.trailing_zeros())
-        //  }
-        match &self.allocated {
-            Atomics::Bool(b) => {
-                match b.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed) {
-                    Ok(false) => Ok(0),
-                    _ => Err(alloc::AllocError),
-                }
+        //  // Atomically set the lowest clear bit, retrying if another thread got there first
+        //  u.fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+        //      // Check if there are any free slots
+        //      (load != !0).then(|| {
+        //          // Shamelessly stolen from: https://stackoverflow.com/questions/31393100/how-to-get-position-of-right-most-set-bit-in-c
+        //          load | (!load & load.wrapping_add(1))
+        //      })
+        //  })
+        //  // The bit set is the lowest clear bit of the value replaced
+        //  .map(|load| (!load).trailing_zeros())
+        #[cfg(feature = "critical-section")]
+        let slot = self
+            .update(|load| {
+                (load != self.full()).then(|| (load | (load + 1), (!load).trailing_zeros()))
+            })
+            .ok_or(AllocError);
+        #[cfg(not(feature = "critical-section"))]
+        let slot = match &self.allocated {
+            Atomics::Bool(b) => b
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .map(|_| 0)
+                .map_err(|_| AllocError),
+            Atomics::U8(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | (!load & load.wrapping_add(1)))
+                })
+                .map(|load| (!load).trailing_zeros())
+                .map_err(|_| AllocError),
+            Atomics::U16(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | (!load & load.wrapping_add(1)))
+                })
+                .map(|load| (!load).trailing_zeros())
+                .map_err(|_| AllocError),
+            Atomics::U32(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | (!load & load.wrapping_add(1)))
+                })
+                .map(|load| (!load).trailing_zeros())
+                .map_err(|_| AllocError),
+            Atomics::U64(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | (!load & load.wrapping_add(1)))
+                })
+                .map(|load| (!load).trailing_zeros())
+                .map_err(|_| AllocError),
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | (!load & load.wrapping_add(1)))
+                })
+                .map(|load| (!load).trailing_zeros())
+                .map_err(|_| AllocError),
+        };
+        #[cfg(not(feature = "critical-section"))]
+        if slot.is_ok() {
+            self.raise_used(1);
+        }
+        slot
+    }
+
+    /// Marks the lowest free slot as allocated with a single compare and swap, failing rather
+    /// than retrying if another thread changed the bitmap first
+    pub(crate) fn allocate_once(&self) -> Result<u32> {
+        if let Some(list) = &self.free_list {
+            return self.take_listed(list.pop(self, false));
+        }
+        // A critical section is already a single bounded attempt
+        #[cfg(feature = "critical-section")]
+        return self.allocate();
+        #[cfg(not(feature = "critical-section"))]
+        {
+            macro_rules! once {
+                ($u:expr) => {{
+                    let load = $u.load(Ordering::Relaxed);
+                    if load == !0 {
+                        Err(AllocError)
+                    } else {
+                        $u.compare_exchange(
+                            load,
+                            load | (load + 1),
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        )
+                        .map(|_| (!load).trailing_zeros())
+                        .map_err(|_| AllocError)
+                    }
+                }};
             }
-            Atomics::U8(u) => {
-                let load = u.load(Ordering::Acquire);
-                if !load == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    let set_bit = !load & (load + 1);
-                    u.store(load | set_bit, Ordering::Release);
-                    Ok(set_bit.trailing_zeros())
-                }
+            let slot = match &self.allocated {
+                Atomics::Bool(b) => b
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .map(|_| 0)
+                    .map_err(|_| AllocError),
+                Atomics::U8(u) => once!(u),
+                Atomics::U16(u) => once!(u),
+                Atomics::U32(u) => once!(u),
+                Atomics::U64(u) => once!(u),
+                #[cfg(feature = "u128")]
+                Atomics::U128(u) => once!(u),
+            };
+            if slot.is_ok() {
+                self.raise_used(1);
             }
-            Atomics::U16(u) => {
-                let load = u.load(Ordering::Acquire);
-                if !load == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    let set_bit = !load & (load + 1);
-                    u.store(load | set_bit, Ordering::Release);
-                    Ok(set_bit.trailing_zeros())
-                }
+            slot
+        }
+    }
+
+    /// Marks the slot popped off the free list, if any, as allocated
+    fn take_listed(&self, slot: Option<u32>) -> Result<u32> {
+        let slot = slot.ok_or(AllocError)?;
+        #[cfg(feature = "critical-section")]
+        self.update(|load| Some((load | 1 << slot, ())));
+        #[cfg(not(feature = "critical-section"))]
+        {
+            self.allocated.set_bits(1 << slot);
+            self.raise_used(1);
+        }
+        Ok(slot)
+    }
+
+    pub(crate) fn deallocate(&self, index: u32) -> Result<()> {
+        // Clearing the bit atomically reports whether it was set, so a double free is detected
+        // even when it races with another free
+        #[cfg(feature = "critical-section")]
+        let freed = self
+            .update(|load| (load & 1 << index != 0).then(|| (load & !(1 << index), ())))
+            .is_some();
+        #[cfg(not(feature = "critical-section"))]
+        let freed = match &self.allocated {
+            Atomics::Bool(b) => b
+                .compare_exchange(true, false, Ordering::Release, Ordering::Relaxed)
+                .is_ok(),
+            Atomics::U8(u) => u.fetch_and(!(1 << index), Ordering::Release) & (1 << index) != 0,
+            Atomics::U16(u) => u.fetch_and(!(1 << index), Ordering::Release) & (1 << index) != 0,
+            Atomics::U32(u) => u.fetch_and(!(1 << index), Ordering::Release) & (1 << index) != 0,
+            Atomics::U64(u) => u.fetch_and(!(1 << index), Ordering::Release) & (1 << index) != 0,
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.fetch_and(!(1 << index), Ordering::Release) & (1 << index) != 0,
+        };
+        if freed {
+            #[cfg(not(feature = "critical-section"))]
+            self.used.0.fetch_sub(1, Ordering::Relaxed);
+            if let Some(list) = &self.free_list {
+                // SAFETY: the slot was just freed, so its slab is no longer used
+                unsafe { list.push(self, index) };
             }
-            Atomics::U32(u) => {
-                let load = u.load(Ordering::Acquire);
-                if !load == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    let set_bit = !load & (load + 1);
-                    u.store(load | set_bit, Ordering::Release);
-                    Ok(set_bit.trailing_zeros())
-                }
+            Ok(())
+        } else {
+            Err(AllocError)
+        }
+    }
+
+    /// The mask of the lowest run of `count` adjacent slots clear in `allocated`, if any
+    fn lowest_run(&self, allocated: Bitmap, count: u32) -> Option<Bitmap> {
+        let last = self.total_slots().checked_sub(count)?;
+        let run = Bitmap::MAX >> (Bitmap::BITS - count);
+        (0..=last)
+            .map(|start| run << start)
+            .find(|mask| allocated & mask == 0)
+    }
+
+    /// Marks the lowest run of `count` adjacent free slots of a spanning section as allocated,
+    /// returning the first, and records the rest as continuing it
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    pub(crate) fn allocate_run(&self, count: u32) -> Result<u32> {
+        let runs = self.runs.as_ref().ok_or(AllocError)?;
+        if count <= 1 {
+            return self.allocate();
+        }
+        #[cfg(feature = "critical-section")]
+        let mask = self
+            .update(|load| {
+                let mask = self.lowest_run(load, count)?;
+                Some((load | mask, mask))
+            })
+            .ok_or(AllocError)?;
+        #[cfg(not(feature = "critical-section"))]
+        let mask = {
+            macro_rules! run {
+                ($u:expr, $t:ty) => {
+                    $u.fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                        Some(load | self.lowest_run(Bitmap::from(load), count)? as $t)
+                    })
+                    .map(|load| {
+                        self.lowest_run(Bitmap::from(load), count)
+                            .expect("Run was free")
+                    })
+                    .map_err(|_| AllocError)
+                };
             }
-            Atomics::U64(u) => {
-                let load = u.load(Ordering::Acquire);
-                if !load == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    let set_bit = !load & (load + 1);
-                    u.store(load | set_bit, Ordering::Release);
-                    Ok(set_bit.trailing_zeros())
+            let mask = match &self.allocated {
+                Atomics::Bool(_) => Err(AllocError),
+                Atomics::U8(u) => run!(u, u8),
+                Atomics::U16(u) => run!(u, u16),
+                Atomics::U32(u) => run!(u, u32),
+                Atomics::U64(u) => run!(u, u64),
+                #[cfg(feature = "u128")]
+                Atomics::U128(u) => run!(u, u128),
+            }?;
+            self.raise_used(count);
+            mask
+        };
+        // Every slot after the first continues the run
+        runs.set_bits(mask & (mask - 1));
+        Ok(mask.trailing_zeros())
+    }
+
+    /// The mask of the slots of the allocation starting at slot `index`, more than one if it
+    /// spans a run
+    pub(crate) fn run_of(&self, index: u32) -> Bitmap {
+        let Some(runs) = &self.runs else {
+            return 1 << index;
+        };
+        let len = (runs.load_bits() >> index >> 1).trailing_ones() + 1;
+        (Bitmap::MAX >> (Bitmap::BITS - len)) << index
+    }
+
+    /// The slots continuing a run, a set bit for every slot of a spanning allocation after its
+    /// first
+    pub(crate) fn run_bits(&self) -> Bitmap {
+        self.runs.as_ref().map_or(0, Atomics::load_bits)
+    }
+
+    /// Marks exactly the slots in `bitmap` as allocated, those in `runs` continuing a run as
+    /// [`run_bits`](Section::run_bits) reports them, and threads the free list through the rest
+    ///
+    /// # Safety
+    /// No slab of the section may be in use and no allocation or free may run at the same time
+    pub(crate) unsafe fn restore(&self, bitmap: Bitmap, runs: Bitmap) {
+        self.reset();
+        #[cfg(feature = "critical-section")]
+        self.update(|_| Some((bitmap, ())));
+        #[cfg(not(feature = "critical-section"))]
+        {
+            self.allocated.set_bits(bitmap);
+            self.raise_used(bitmap.count_ones());
+        }
+        if let Some(current) = &self.runs {
+            current.set_bits(runs);
+        }
+        if let Some(list) = &self.free_list {
+            list.relink(self);
+        }
+    }
+
+    /// Marks up to `count` of the lowest free slots as allocated with a single atomic operation,
+    /// returning the mask of the slots claimed
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    pub(crate) fn allocate_batch(&self, count: u32) -> Bitmap {
+        if let Some(list) = &self.free_list {
+            let mut mask = 0;
+            for _ in 0..count {
+                match self.take_listed(list.pop(self, true)) {
+                    Ok(slot) => mask |= 1 << slot,
+                    Err(_) => break,
                 }
             }
+            return mask;
+        }
+        // The lowest `count` set bits of `free`
+        fn lowest(mut free: Bitmap, count: u32) -> Bitmap {
+            let mut mask = 0;
+            for _ in 0..count.min(free.count_ones()) {
+                mask |= free & free.wrapping_neg();
+                free &= free - 1;
+            }
+            mask
+        }
+        #[cfg(feature = "critical-section")]
+        return self
+            .update(|load| {
+                let mask = lowest(!load & self.full(), count);
+                Some((load | mask, mask))
+            })
+            .unwrap_or(0);
+        #[cfg(not(feature = "critical-section"))]
+        let mask = match &self.allocated {
+            Atomics::Bool(b) => Bitmap::from(
+                count > 0
+                    && b.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok(),
+            ),
+            Atomics::U8(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | lowest(Bitmap::from(!load), count) as u8)
+                })
+                .map_or(0, |load| lowest(Bitmap::from(!load), count)),
+            Atomics::U16(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | lowest(Bitmap::from(!load), count) as u16)
+                })
+                .map_or(0, |load| lowest(Bitmap::from(!load), count)),
+            Atomics::U32(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | lowest(Bitmap::from(!load), count) as u32)
+                })
+                .map_or(0, |load| lowest(Bitmap::from(!load), count)),
+            Atomics::U64(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | lowest(Bitmap::from(!load), count) as u64)
+                })
+                .map_or(0, |load| lowest(Bitmap::from(!load), count)),
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |load| {
+                    (load != !0).then(|| load | lowest(!load, count))
+                })
+                .map_or(0, |load| lowest(!load, count)),
+        };
+        #[cfg(not(feature = "critical-section"))]
+        {
+            self.raise_used(mask.count_ones());
+            mask
         }
     }
 
-    pub(crate) fn deallocate(&self, index: u32) -> Result<()> {
+    /// Marks every slot in `mask`, all of which must be allocated, as free with a single atomic
+    /// operation
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    pub(crate) fn deallocate_batch(&self, mask: Bitmap) {
+        // The slots no longer continue a run once they are free
+        if let Some(runs) = &self.runs {
+            runs.clear_bits(mask);
+        }
+        #[cfg(feature = "critical-section")]
+        self.update(|load| Some((load & !mask, ())));
+        #[cfg(not(feature = "critical-section"))]
         match &self.allocated {
             Atomics::Bool(b) => {
-                match b.compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed) {
-                    Ok(true) => Ok(()),
-                    _ => Err(alloc::AllocError),
+                if mask & 1 != 0 {
+                    b.store(false, Ordering::Release)
                 }
             }
             Atomics::U8(u) => {
-                let load = u.load(Ordering::Acquire);
-                let set_bit = 1u8 << index;
-                if load & set_bit == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    u.store(load & !set_bit, Ordering::Release);
-                    Ok(())
-                }
+                u.fetch_and(!(mask as u8), Ordering::Release);
             }
             Atomics::U16(u) => {
-                let load = u.load(Ordering::Acquire);
-                let set_bit = 1u16 << index;
-                if load & set_bit == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    u.store(load & !set_bit, Ordering::Release);
-                    Ok(())
-                }
+                u.fetch_and(!(mask as u16), Ordering::Release);
             }
             Atomics::U32(u) => {
-                let load = u.load(Ordering::Acquire);
-                let set_bit = 1u32 << index;
-                if load & set_bit == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    u.store(load & !set_bit, Ordering::Release);
-                    Ok(())
-                }
+                u.fetch_and(!(mask as u32), Ordering::Release);
             }
             Atomics::U64(u) => {
-                let load = u.load(Ordering::Acquire);
-                let set_bit = 1u64 << index;
-                if load & set_bit == 0 {
-                    Err(alloc::AllocError)
-                } else {
-                    u.store(load & !set_bit, Ordering::Release);
-                    Ok(())
+                u.fetch_and(!(mask as u64), Ordering::Release);
+            }
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => {
+                u.fetch_and(!mask, Ordering::Release);
+            }
+        }
+        #[cfg(not(feature = "critical-section"))]
+        self.used.0.fetch_sub(mask.count_ones(), Ordering::Relaxed);
+        if let Some(list) = &self.free_list {
+            let mut slots = mask;
+            while slots != 0 {
+                // SAFETY: the slots were just freed, so their slabs are no longer used
+                unsafe { list.push(self, slots.trailing_zeros()) };
+                slots &= slots - 1;
+            }
+        }
+    }
+
+    /// Clears a slab about to be freed: wipes it with volatile writes if the section is sensitive,
+    /// zeroes it if the section is pre-zeroed, otherwise fills it with [`POISON`] when the
+    /// `poison` feature is enabled
+    ///
+    /// # Safety
+    /// `slab` must be a slab of this section that is allocated and no longer used
+    pub(crate) unsafe fn clear_freed(&self, slab: ptr::NonNull<[u8]>) {
+        #[cfg(feature = "zeroize")]
+        if self.sensitive {
+            zeroize::Zeroize::zeroize(core::slice::from_raw_parts_mut(
+                slab.cast::<u8>().as_ptr(),
+                slab.len(),
+            ));
+            return;
+        }
+        if self.zeroed {
+            slab.cast::<u8>().write_bytes(0, slab.len());
+        } else if cfg!(feature = "poison") {
+            let pattern = POISON.to_be_bytes();
+            let start = slab.cast::<u8>();
+            for i in 0..slab.len() {
+                start.add(i).write(pattern[i % pattern.len()]);
+            }
+        }
+    }
+
+    /// Moves the allocation in slot `from` to the free slot `to`, leaving the number of slots in
+    /// use and the peak unchanged
+    pub(crate) fn move_slot(&self, from: u32, to: u32) {
+        #[cfg(feature = "critical-section")]
+        self.update(|load| Some((load & !(1 << from) | 1 << to, ())));
+        #[cfg(not(feature = "critical-section"))]
+        {
+            self.allocated.set_bits(1 << to);
+            self.allocated.clear_bits(1 << from);
+        }
+    }
+
+    /// Carries the owner, location and tag of slot `from` over to every slot of the allocation
+    /// at slot `to` of `target`, for an allocation moved there
+    pub(crate) fn carry_meta(&self, from: u32, target: &Section, to: u32) {
+        #[cfg(any(feature = "amp", feature = "leak-check", feature = "tags"))]
+        {
+            let source = &self.meta[from as usize];
+            let mut slots = target.run_of(to);
+            while slots != 0 {
+                let meta = &target.meta[slots.trailing_zeros() as usize];
+                #[cfg(feature = "amp")]
+                meta.owner
+                    .store(source.owner.load(Ordering::Relaxed), Ordering::Relaxed);
+                #[cfg(feature = "leak-check")]
+                meta.location
+                    .store(source.location.load(Ordering::Relaxed), Ordering::Relaxed);
+                #[cfg(feature = "tags")]
+                meta.tag
+                    .store(source.tag.load(Ordering::Relaxed), Ordering::Relaxed);
+                slots &= slots - 1;
+            }
+        }
+        #[cfg(not(any(feature = "amp", feature = "leak-check", feature = "tags")))]
+        let _ = (from, target, to);
+    }
+
+    /// Marks slot `index` as allocated if it is free
+    pub(crate) fn claim(&self, index: u32) -> Result<()> {
+        // A slot in a free list cannot be taken out of the middle of it
+        if self.free_list.is_some() {
+            return Err(AllocError);
+        }
+        #[cfg(feature = "critical-section")]
+        let claimed = self
+            .update(|load| (load & 1 << index == 0).then(|| (load | 1 << index, ())))
+            .is_some();
+        #[cfg(not(feature = "critical-section"))]
+        let claimed = self.mark(index);
+        if claimed {
+            #[cfg(not(feature = "critical-section"))]
+            self.raise_used(1);
+            Ok(())
+        } else {
+            Err(AllocError)
+        }
+    }
+
+    /// Runs `f` on slot `index` if it is free, keeping the slot from being allocated meanwhile
+    /// without counting it as in use. Returns whether the slot was free
+    pub(crate) fn with_free(&self, index: u32, f: impl FnOnce()) -> bool {
+        // The free slabs of a free list section hold its links
+        if self.free_list.is_some() {
+            return false;
+        }
+        #[cfg(feature = "critical-section")]
+        {
+            critical_section::with(|_| {
+                let free = !self.is_allocated(index);
+                if free {
+                    f();
                 }
+                free
+            })
+        }
+        #[cfg(not(feature = "critical-section"))]
+        {
+            if !self.mark(index) {
+                return false;
             }
+            f();
+            self.allocated.clear_bits(1 << index);
+            true
+        }
+    }
+
+    /// Sets the bit of slot `index`, returning whether it was clear
+    #[cfg(not(feature = "critical-section"))]
+    fn mark(&self, index: u32) -> bool {
+        match &self.allocated {
+            Atomics::Bool(b) => b
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok(),
+            Atomics::U8(u) => u.fetch_or(1 << index, Ordering::Acquire) & (1 << index) == 0,
+            Atomics::U16(u) => u.fetch_or(1 << index, Ordering::Acquire) & (1 << index) == 0,
+            Atomics::U32(u) => u.fetch_or(1 << index, Ordering::Acquire) & (1 << index) == 0,
+            Atomics::U64(u) => u.fetch_or(1 << index, Ordering::Acquire) & (1 << index) == 0,
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.fetch_or(1 << index, Ordering::Acquire) & (1 << index) == 0,
+        }
+    }
+
+    /// Whether slot `index` is allocated
+    pub(crate) fn is_allocated(&self, index: u32) -> bool {
+        match &self.allocated {
+            Atomics::Bool(b) => b.load(Ordering::Acquire),
+            Atomics::U8(u) => u.load(Ordering::Acquire) & (1 << index) != 0,
+            Atomics::U16(u) => u.load(Ordering::Acquire) & (1 << index) != 0,
+            Atomics::U32(u) => u.load(Ordering::Acquire) & (1 << index) != 0,
+            Atomics::U64(u) => u.load(Ordering::Acquire) & (1 << index) != 0,
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.load(Ordering::Acquire) & (1 << index) != 0,
+        }
+    }
+
+    /// Marks every slot as free
+    pub(crate) fn reset(&self) {
+        match &self.allocated {
+            Atomics::Bool(b) => b.store(false, Ordering::Release),
+            Atomics::U8(u) => u.store(0, Ordering::Release),
+            Atomics::U16(u) => u.store(0, Ordering::Release),
+            Atomics::U32(u) => u.store(0, Ordering::Release),
+            Atomics::U64(u) => u.store(0, Ordering::Release),
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.store(0, Ordering::Release),
+        }
+        if let Some(runs) = &self.runs {
+            runs.clear_bits(Bitmap::MAX);
+        }
+        self.used.0.store(0, Ordering::Relaxed);
+    }
+
+    /// The gauge counting the section's allocated slots
+    pub fn gauge(&self) -> &Gauge {
+        &self.used
+    }
+
+    /// Whether fewer slots are free than the section's watermark
+    pub fn is_low(&self) -> bool {
+        self.low.load(Ordering::Relaxed)
+    }
+
+    /// The most slots that have been allocated at once
+    pub fn peak(&self) -> u32 {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Counts `slots` more slots as allocated, raising the peak if it is passed
+    #[cfg(not(feature = "critical-section"))]
+    fn raise_used(&self, slots: u32) {
+        let used = self.used.0.fetch_add(slots, Ordering::Relaxed) + slots;
+        self.peak.fetch_max(used, Ordering::Relaxed);
+    }
+
+    /// The allocation bitmap, with a set bit for each allocated slot
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    pub(crate) fn bitmap(&self) -> Bitmap {
+        match &self.allocated {
+            Atomics::Bool(u) => Bitmap::from(u.load(Ordering::Relaxed)),
+            Atomics::U8(u) => Bitmap::from(u.load(Ordering::Relaxed)),
+            Atomics::U16(u) => Bitmap::from(u.load(Ordering::Relaxed)),
+            Atomics::U32(u) => Bitmap::from(u.load(Ordering::Relaxed)),
+            Atomics::U64(u) => Bitmap::from(u.load(Ordering::Relaxed)),
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.load(Ordering::Relaxed),
         }
     }
 
+    /// The bitmap with every slot allocated
+    pub(crate) const fn full(&self) -> Bitmap {
+        Bitmap::MAX >> (Bitmap::BITS - self.total_slots())
+    }
+
+    /// Replaces the allocation bitmap with the one `f` derives from it, if any, inside a critical
+    /// section so no read-modify-write atomic operations are needed
+    // `Bitmap` is `u64` unless the `u128` feature is enabled
+    #[cfg(feature = "critical-section")]
+    #[allow(clippy::unnecessary_cast)]
+    fn update<R>(&self, f: impl FnOnce(Bitmap) -> Option<(Bitmap, R)>) -> Option<R> {
+        critical_section::with(|_| {
+            let (bitmap, result) = f(self.bitmap())?;
+            match &self.allocated {
+                Atomics::Bool(b) => b.store(bitmap & 1 != 0, Ordering::Release),
+                Atomics::U8(u) => u.store(bitmap as u8, Ordering::Release),
+                Atomics::U16(u) => u.store(bitmap as u16, Ordering::Release),
+                Atomics::U32(u) => u.store(bitmap as u32, Ordering::Release),
+                Atomics::U64(u) => u.store(bitmap as u64, Ordering::Release),
+                #[cfg(feature = "u128")]
+                Atomics::U128(u) => u.store(bitmap, Ordering::Release),
+            }
+            let used = bitmap.count_ones();
+            self.used.0.store(used, Ordering::Relaxed);
+            if used > self.peak.load(Ordering::Relaxed) {
+                self.peak.store(used, Ordering::Relaxed);
+            }
+            Some(result)
+        })
+    }
+
     /// The amount of slots unallocated
     pub fn free_slots(&self) -> u32 {
         match &self.allocated {
@@ -185,17 +1215,21 @@ impl Section {
             Atomics::U16(u) => u.load(Ordering::Relaxed).count_zeros(),
             Atomics::U32(u) => u.load(Ordering::Relaxed).count_zeros(),
             Atomics::U64(u) => u.load(Ordering::Relaxed).count_zeros(),
+            #[cfg(feature = "u128")]
+            Atomics::U128(u) => u.load(Ordering::Relaxed).count_zeros(),
         }
     }
 
     /// The total number of slots available
-    pub fn total_slots(&self) -> u32 {
+    pub const fn total_slots(&self) -> u32 {
         match &self.allocated {
             Atomics::Bool(_) => 1,
             Atomics::U8(_) => 8,
             Atomics::U16(_) => 16,
             Atomics::U32(_) => 32,
             Atomics::U64(_) => 64,
+            #[cfg(feature = "u128")]
+            Atomics::U128(_) => 128,
         }
     }
 
@@ -205,6 +1239,35 @@ impl Section {
     }
 }
 
+impl fmt::Debug for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let slots = self.total_slots();
+        f.debug_struct("Section")
+            .field("size", &self.size)
+            .field("slots", &slots)
+            .field("free", &self.free_slots())
+            .field(
+                "bitmap",
+                &format_args!("{:01$b}", self.bitmap(), slots as usize),
+            )
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Section {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "Section {{ size: {=usize}, slots: {=u32}, free: {=u32}, bitmap: {:b} }}",
+            self.size,
+            self.total_slots(),
+            self.free_slots(),
+            self.bitmap()
+        );
+    }
+}
+
 #[cfg(test)]
 mod test {
     macro_rules! tests {
@@ -243,6 +1306,104 @@ mod test {
         (u64_alloc, u64_dealloc, u64, atomic::AtomicU64)
     }
 
+    #[cfg(feature = "u128")]
+    tests! {
+        (u128_alloc, u128_dealloc, u128, portable_atomic::AtomicU128)
+    }
+
+    #[test]
+    fn with_capacity() {
+        use crate::section::*;
+        assert_eq!(Section::with_capacity(8, 1).total_slots(), 1);
+        assert_eq!(Section::with_capacity(8, 2).total_slots(), 8);
+        assert_eq!(Section::with_capacity(8, 20).total_slots(), 32);
+        assert_eq!(Section::with_capacity(8, 64).free_slots(), 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many slots")]
+    fn with_capacity_too_many() {
+        use crate::section::*;
+        Section::with_capacity(8, MAX_SLOTS + 1);
+    }
+
+    #[test]
+    fn claim() {
+        use crate::section::*;
+        let section: Section = Section::new(0, atomic::AtomicU16::new(0));
+        assert!(section.claim(3).is_ok());
+        assert!(section.claim(3).is_err());
+        assert!(section.claim(15).is_ok());
+        assert!(section.free_slots() == 14);
+        assert!(section.allocate() == Ok(0));
+        assert!(section.deallocate(3).is_ok());
+        assert!(section.claim(3).is_ok());
+    }
+
+    #[test]
+    fn concurrent() {
+        extern crate std;
+        use crate::section::*;
+        let section: Section = Section::new(0, atomic::AtomicU64::new(0));
+        let claimed = atomic::AtomicU64::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..16 {
+                        let slot = section.allocate().unwrap();
+                        // No slot is handed out twice
+                        let bit = 1 << slot;
+                        assert_eq!(claimed.fetch_or(bit, Ordering::Relaxed) & bit, 0);
+                    }
+                });
+            }
+        });
+        assert_eq!(claimed.load(Ordering::Relaxed), u64::MAX);
+        assert_eq!(section.gauge().get(), 64);
+        assert!(section.allocate().is_err());
+    }
+
+    #[test]
+    fn gauge() {
+        use crate::section::*;
+        let section: Section = Section::new(0, atomic::AtomicU8::new(0b101));
+        let gauge = section.gauge();
+        assert_eq!(gauge.get(), 2);
+        assert!(section.allocate().is_ok());
+        assert!(section.claim(7).is_ok());
+        assert!(section.claim(7).is_err());
+        assert_eq!(gauge.get(), 4);
+        assert!(section.deallocate(0).is_ok());
+        assert!(section.deallocate(0).is_err());
+        assert_eq!(gauge.get(), 3);
+        section.reset();
+        assert_eq!(gauge.get(), 0);
+    }
+
+    #[test]
+    fn batch() {
+        use crate::section::*;
+        let section: Section = Section::new(0, atomic::AtomicU8::new(0b101));
+        assert_eq!(section.allocate_batch(3), 0b11010);
+        assert_eq!(section.gauge().get(), 5);
+        assert_eq!(section.allocate_batch(8), 0b11100000);
+        assert_eq!(section.allocate_batch(1), 0);
+        section.deallocate_batch(0b11010);
+        assert_eq!(section.free_slots(), 3);
+        assert_eq!(section.allocate(), Ok(1));
+    }
+
+    #[test]
+    fn debug() {
+        extern crate std;
+        use crate::section::*;
+        let section: Section = Section::new(8, atomic::AtomicU8::new(0b101));
+        assert_eq!(
+            std::format!("{section:?}"),
+            "Section { size: 8, slots: 8, free: 6, bitmap: 00000101 }"
+        );
+    }
+
     #[test]
     fn bool_alloc() {
         use crate::section::*;
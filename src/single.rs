@@ -0,0 +1,97 @@
+use crate::api::{AllocError, Allocator};
+use core::{alloc, cell, marker, ptr};
+
+/// Wrapper stating an allocator is only used from the thread that owns it. It can be moved to
+/// another thread but not shared between them, so the type system rejects code that would
+/// allocate from several threads at once.
+///
+/// Wrapping does not relax the orderings of the allocator's atomic operations: they are chosen
+/// by its sections, which do not know they are wrapped, and the allocator may be unwrapped with
+/// [`into_inner`](SingleThread::into_inner) and shared again. For a single thread without
+/// atomics use [`LocalSlabAllocator`](crate::LocalSlabAllocator)
+pub struct SingleThread<A> {
+    allocator: A,
+    _unsync: marker::PhantomData<cell::Cell<()>>,
+}
+
+impl<A> SingleThread<A> {
+    /// Wraps `allocator`
+    pub const fn new(allocator: A) -> Self {
+        Self {
+            allocator,
+            _unsync: marker::PhantomData,
+        }
+    }
+
+    /// The wrapped allocator, for its inherent methods
+    pub fn get_mut(&mut self) -> &mut A {
+        &mut self.allocator
+    }
+
+    /// Unwraps the allocator
+    pub fn into_inner(self) -> A {
+        self.allocator
+    }
+}
+
+unsafe impl<A: Allocator> Allocator for SingleThread<A> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.allocator.allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.allocator.allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        self.allocator.deallocate(ptr, layout)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: ptr::NonNull<u8>,
+        old_layout: alloc::Layout,
+        new_layout: alloc::Layout,
+    ) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        self.allocator.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn single_thread() {
+        let mut buf = Aligned([0u8; 64]);
+        let mut allocator = SingleThread::new(
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf.0[..]).unwrap(),
+        );
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert_eq!(allocator.get_mut().gauges()[0].get(), 1);
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+        assert_eq!(allocator.into_inner().gauges()[0].get(), 0);
+    }
+}
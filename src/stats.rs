@@ -0,0 +1,291 @@
+use crate::atomic::{AtomicUsize, Ordering};
+use crate::event::Event;
+use crate::section::Section;
+use crate::SlabAllocator;
+
+/// The number of buckets of [`Fragmentation::histogram`]
+pub const HISTOGRAM_BUCKETS: usize = 16;
+
+/// Running totals of allocator activity
+pub(crate) struct Counters {
+    allocations: AtomicUsize,
+    deallocations: AtomicUsize,
+    failures: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+/// Running totals of the sizes requested of a section
+pub(crate) struct Requests {
+    requests: AtomicUsize,
+    requested: AtomicUsize,
+    served: AtomicUsize,
+    histogram: [AtomicUsize; HISTOGRAM_BUCKETS],
+}
+
+impl Requests {
+    pub(crate) const fn new() -> Self {
+        Self {
+            requests: AtomicUsize::new(0),
+            requested: AtomicUsize::new(0),
+            served: AtomicUsize::new(0),
+            histogram: [const { AtomicUsize::new(0) }; HISTOGRAM_BUCKETS],
+        }
+    }
+
+    /// Records a request for `size` bytes served by a slab of `served` bytes, more than the
+    /// section's slab size if it spans a run
+    pub(crate) fn record(&self, size: usize, served: usize) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.requested.fetch_add(size, Ordering::Relaxed);
+        self.served.fetch_add(served, Ordering::Relaxed);
+        // Sizes up to 2^i bytes fall in bucket i
+        let bucket = (usize::BITS - size.saturating_sub(1).leading_zeros()) as usize;
+        self.histogram[bucket.min(HISTOGRAM_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            allocations: AtomicUsize::new(0),
+            deallocations: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+            bytes: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn allocated(&self, bytes: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn deallocated(&self) {
+        self.deallocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn failed(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Stats {
+        Stats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            deallocations: self.deallocations.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts of allocator activity, since creation or over a scope
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// Successful allocations
+    pub allocations: usize,
+    /// Deallocations
+    pub deallocations: usize,
+    /// Allocations that could not be satisfied
+    pub failures: usize,
+    /// Bytes of slabs handed out by successful allocations
+    pub bytes: usize,
+}
+
+impl Stats {
+    /// The activity between `earlier` and `self`
+    pub fn since(&self, earlier: &Stats) -> Stats {
+        Stats {
+            allocations: self.allocations.wrapping_sub(earlier.allocations),
+            deallocations: self.deallocations.wrapping_sub(earlier.deallocations),
+            failures: self.failures.wrapping_sub(earlier.failures),
+            bytes: self.bytes.wrapping_sub(earlier.bytes),
+        }
+    }
+}
+
+/// How well the requests a section served fit its slabs, from
+/// [`SlabAllocator::fragmentation_report`]. Only requests made through the allocator are
+/// counted, not those served from [`Magazines`](crate::Magazines)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Fragmentation {
+    /// The size of the section's slabs
+    pub size: usize,
+    /// The number of requests served
+    pub requests: usize,
+    /// The bytes requested
+    pub requested: usize,
+    /// The bytes of the slabs served beyond those requested, lost to internal fragmentation
+    pub wasted: usize,
+    /// The number of requests of each size: bucket `i` counts sizes above `2^(i-1)` and up to
+    /// `2^i` bytes, the last bucket every larger size too
+    pub histogram: [usize; HISTOGRAM_BUCKETS],
+}
+
+/// Guard measuring allocator activity from its creation, returned by
+/// [`SlabAllocator::scoped_stats`]. The activity is reported to the allocator's observer as an
+/// [`Event::ScopeEnded`] when dropped
+pub struct ScopedStats<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    start: Stats,
+}
+
+impl<'a, 'm, const N: usize> ScopedStats<'a, 'm, N> {
+    /// The activity since the guard was created
+    pub fn delta(&self) -> Stats {
+        self.allocator.stats().since(&self.start)
+    }
+}
+
+impl<'a, 'm, const N: usize> Drop for ScopedStats<'a, 'm, N> {
+    fn drop(&mut self) {
+        if let Some(observer) = self.allocator.observer {
+            observer(&Event::ScopeEnded(self.delta()));
+        }
+    }
+}
+
+impl Section {
+    /// The activity of the section since the allocator was created
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// The activity of the allocator since it was created.
+    /// Note that activity from every thread is counted
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+
+    /// The activity of each section since the allocator was created. Failures are counted
+    /// against the section the layout would have been served from
+    pub fn section_stats(&self) -> [Stats; N] {
+        core::array::from_fn(|i| self.blocks[i].stats())
+    }
+
+    /// How much of each section's slabs the requests it served used, to retune the size classes
+    pub fn fragmentation_report(&self) -> [Fragmentation; N] {
+        core::array::from_fn(|i| {
+            let section = &self.blocks[i];
+            let counters = &section.requests;
+            let requests = counters.requests.load(Ordering::Relaxed);
+            let requested = counters.requested.load(Ordering::Relaxed);
+            let served = counters.served.load(Ordering::Relaxed);
+            Fragmentation {
+                size: section.size,
+                requests,
+                requested,
+                wasted: served.saturating_sub(requested),
+                histogram: counters
+                    .histogram
+                    .each_ref()
+                    .map(|bucket| bucket.load(Ordering::Relaxed)),
+            }
+        })
+    }
+
+    /// Starts measuring allocator activity until the returned guard is dropped
+    pub fn scoped_stats(&self) -> ScopedStats<'_, 'm, N> {
+        ScopedStats {
+            allocator: self,
+            start: self.stats(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::stats::Stats;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn scoped_stats() {
+        extern crate std;
+        let mut buf = [0u8; 64];
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0))], &mut buf[..]).unwrap();
+
+        let b = std::boxed::Box::new_in(1u8, &allocator);
+        let scope = allocator.scoped_stats();
+        assert_eq!(scope.delta(), Stats::default());
+
+        drop(b);
+        let _ = allocator.allocate(Layout::new::<[u8; 9]>());
+        let c = std::boxed::Box::new_in(2u8, &allocator);
+        assert_eq!(
+            scope.delta(),
+            Stats {
+                allocations: 1,
+                deallocations: 1,
+                failures: 1,
+                bytes: 8,
+            }
+        );
+        drop(c);
+        assert_eq!(allocator.stats().allocations, 2);
+    }
+
+    #[test]
+    fn section_stats() {
+        let mut buf = [0u8; 64];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicU8::new(0)),
+                Section::new(16, AtomicBool::new(false)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+
+        let layout = Layout::new::<[u8; 12]>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert!(allocator.allocate(layout).is_err());
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+        assert!(allocator.allocate(Layout::new::<[u8; 20]>()).is_err());
+
+        let [small, large] = allocator.section_stats();
+        assert_eq!(small, Stats::default());
+        assert_eq!(
+            large,
+            Stats {
+                allocations: 1,
+                deallocations: 1,
+                failures: 1,
+                bytes: 16,
+            }
+        );
+        assert_eq!(allocator.stats().failures, 2);
+    }
+
+    #[test]
+    fn fragmentation_report() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        for size in [1, 3, 8] {
+            allocator
+                .allocate(Layout::from_size_align(size, 1).unwrap())
+                .unwrap();
+        }
+        allocator.allocate(Layout::new::<[u8; 40]>()).unwrap();
+
+        let [small, large] = allocator.fragmentation_report();
+        assert_eq!((small.requests, small.requested, small.wasted), (3, 12, 12));
+        assert_eq!(small.histogram[..4], [1, 0, 1, 1]);
+        assert_eq!((large.size, large.wasted), (64, 24));
+        assert_eq!(large.histogram[6], 1);
+
+        // A request spanning a run wastes what is left of its last slab
+        let mut buf = [0u8; 8 * 8];
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicU8::new(0)).spanning()], &mut buf[..])
+                .unwrap();
+        allocator.allocate(Layout::new::<[u8; 20]>()).unwrap();
+        let [spanning] = allocator.fragmentation_report();
+        assert_eq!((spanning.requested, spanning.wasted), (20, 4));
+    }
+}
@@ -0,0 +1,92 @@
+/// Alignments a [`SlabStorage`] can have
+pub trait Alignment {
+    /// A zero sized type with the alignment
+    type Type;
+}
+
+/// The alignment of `ALIGN` bytes
+pub struct Align<const ALIGN: usize>;
+
+macro_rules! alignments {
+    ($($align:literal => $name:ident),+) => {
+        $(
+            #[doc(hidden)]
+            #[repr(align($align))]
+            pub struct $name;
+
+            impl Alignment for Align<$align> {
+                type Type = $name;
+            }
+        )+
+    };
+}
+
+alignments! {
+    1 => Align1, 2 => Align2, 4 => Align4, 8 => Align8, 16 => Align16, 32 => Align32,
+    64 => Align64, 128 => Align128, 256 => Align256, 512 => Align512, 1024 => Align1024,
+    2048 => Align2048, 4096 => Align4096
+}
+
+/// `BYTES` bytes of backing memory for an allocator, aligned to `ALIGN` (a power of two up to
+/// 4096), which can be declared in a `static`
+#[repr(C)]
+pub struct SlabStorage<const BYTES: usize, const ALIGN: usize>
+where
+    Align<ALIGN>: Alignment,
+{
+    _align: [<Align<ALIGN> as Alignment>::Type; 0],
+    bytes: [u8; BYTES],
+}
+
+impl<const BYTES: usize, const ALIGN: usize> SlabStorage<BYTES, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    /// Zeroed storage
+    pub const fn new() -> Self {
+        Self {
+            _align: [],
+            bytes: [0; BYTES],
+        }
+    }
+
+    /// The storage as a buffer to pass to an allocator
+    pub const fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+impl<const BYTES: usize, const ALIGN: usize> Default for SlabStorage<BYTES, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+
+    #[test]
+    fn storage() {
+        let mut storage = SlabStorage::<100, 256>::new();
+        assert_eq!(core::mem::align_of_val(&storage), 256);
+        let buf = storage.as_bytes_mut();
+        assert_eq!(buf.len(), 100);
+        assert_eq!(buf.as_ptr() as usize % 256, 0);
+
+        static mut STORAGE: SlabStorage<64, 64> = SlabStorage::new();
+        static ALLOCATOR: SlabAllocator<'static, 1> = SlabAllocator::new_const(
+            [Section::empty(64, 1)],
+            // SAFETY: only the allocator uses the storage
+            unsafe { (*core::ptr::addr_of_mut!(STORAGE)).as_bytes_mut() },
+        );
+        let layout = Layout::from_size_align(64, 64).unwrap();
+        // The storage's alignment carries over to the slab
+        let slab = ALLOCATOR.allocate(layout).unwrap();
+        unsafe { ALLOCATOR.deallocate(slab.cast(), layout) };
+    }
+}
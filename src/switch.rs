@@ -0,0 +1,91 @@
+use crate::api::{AllocError, Allocator};
+use crate::atomic::{AtomicBool, Ordering};
+use crate::SlabAllocator;
+use core::alloc;
+use core::ptr;
+use std::alloc::Global;
+
+/// The allocator a [`SlabOrGlobal`] serves new allocations from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The wrapped [`SlabAllocator`]
+    Slab,
+    /// The system allocator
+    Global,
+}
+
+/// Allocator that can be switched between a [`SlabAllocator`] and [`Global`] at runtime,
+/// to compare pooled and system allocation without rebuilding.
+/// Switching only affects new allocations, existing ones are freed by whichever allocator made them
+pub struct SlabOrGlobal<'a, 'm, const N: usize> {
+    slab: &'a SlabAllocator<'m, N>,
+    global: AtomicBool,
+}
+
+impl<'a, 'm, const N: usize> SlabOrGlobal<'a, 'm, N> {
+    /// Wraps `slab`, initially allocating from `backend`
+    pub fn new(slab: &'a SlabAllocator<'m, N>, backend: Backend) -> Self {
+        Self {
+            slab,
+            global: AtomicBool::new(backend == Backend::Global),
+        }
+    }
+
+    /// Serves new allocations from `backend`
+    pub fn switch(&self, backend: Backend) {
+        self.global
+            .store(backend == Backend::Global, Ordering::Relaxed);
+    }
+
+    /// The allocator new allocations are served from
+    pub fn backend(&self) -> Backend {
+        if self.global.load(Ordering::Relaxed) {
+            Backend::Global
+        } else {
+            Backend::Slab
+        }
+    }
+}
+
+unsafe impl<'a, 'm, const N: usize> Allocator for SlabOrGlobal<'a, 'm, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        match self.backend() {
+            Backend::Slab => self.slab.allocate(layout),
+            Backend::Global => Global.allocate(layout),
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        if self.slab.locate(ptr).is_some() {
+            self.slab.deallocate(ptr, layout)
+        } else {
+            Global.deallocate(ptr, layout)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use core::sync::atomic::AtomicU8;
+
+    #[test]
+    fn switching() {
+        let mut buf = [0u8; 256];
+        let slab = SlabAllocator::new([Section::new(32, AtomicU8::new(0))], &mut buf[..]).unwrap();
+        let allocator = SlabOrGlobal::new(&slab, Backend::Slab);
+
+        let pooled = std::boxed::Box::new_in(1u8, &allocator);
+        assert_eq!(slab.blocks[0].free_slots(), 7);
+
+        allocator.switch(Backend::Global);
+        let mut v = std::vec::Vec::new_in(&allocator);
+        v.extend(0..100u8);
+        assert_eq!(slab.blocks[0].free_slots(), 7);
+
+        drop(pooled);
+        drop(v);
+        assert_eq!(slab.blocks[0].free_slots(), 8);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::SlabError;
+use crate::SlabAllocator;
+use core::sync::atomic::Ordering;
+use core::{alloc, ptr};
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Allocates a slab like [`try_allocate`](SlabAllocator::try_allocate), recording `tag`,
+    /// such as the id of the calling task, against it. Allocations made any other way have tag 0
+    #[cfg_attr(feature = "leak-check", track_caller)]
+    pub fn allocate_tagged(
+        &self,
+        layout: alloc::Layout,
+        tag: u16,
+    ) -> Result<ptr::NonNull<[u8]>, SlabError> {
+        let slab = self.try_allocate(layout)?;
+        if let Some((_, section, slot)) = self.locate(slab.cast()) {
+            let mut slots = section.run_of(slot);
+            while slots != 0 {
+                section.meta[slots.trailing_zeros() as usize]
+                    .tag
+                    .store(tag, Ordering::Relaxed);
+                slots &= slots - 1;
+            }
+        }
+        Ok(slab)
+    }
+
+    /// The tag of the allocation `ptr` points into
+    pub fn tag_of(&self, ptr: ptr::NonNull<u8>) -> Option<u16> {
+        let (_, section, slot) = self.locate(ptr)?;
+        section
+            .is_allocated(slot)
+            .then(|| section.meta[slot as usize].tag.load(Ordering::Relaxed))
+    }
+
+    /// The bytes of the slabs allocated with `tag`, including those of sections added since the
+    /// allocator was created
+    pub fn bytes_used_by(&self, tag: u16) -> usize {
+        self.blocks
+            .iter()
+            .chain(self.extra())
+            .map(|section| {
+                let mut slots = section.bitmap();
+                let mut bytes = 0;
+                while slots != 0 {
+                    let slot = slots.trailing_zeros() as usize;
+                    if section.meta[slot].tag.load(Ordering::Relaxed) == tag {
+                        bytes += section.size;
+                    }
+                    slots &= slots - 1;
+                }
+                bytes
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+
+    #[test]
+    fn tags() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        let small = allocator.allocate_tagged(Layout::new::<u64>(), 3).unwrap();
+        let large = allocator
+            .allocate_tagged(Layout::new::<[u8; 40]>(), 3)
+            .unwrap();
+        allocator.allocate(Layout::new::<u64>()).unwrap();
+        assert_eq!(allocator.bytes_used_by(3), 8 + 64);
+        assert_eq!(allocator.bytes_used_by(0), 8);
+        assert_eq!(allocator.tag_of(large.cast()), Some(3));
+
+        unsafe { allocator.deallocate(small.cast(), Layout::new::<u64>()) };
+        assert_eq!(allocator.tag_of(small.cast()), None);
+        assert_eq!(allocator.bytes_used_by(3), 64);
+        // A slab handed out again starts untagged
+        let slab = allocator.allocate(Layout::new::<u64>()).unwrap();
+        assert_eq!(allocator.tag_of(slab.cast()), Some(0));
+    }
+
+    #[test]
+    fn moved() {
+        let mut buf = test::buffer();
+        let allocator = test::allocator(&mut buf);
+        let layout = Layout::new::<u64>();
+        let first = allocator.allocate(layout).unwrap();
+        let tagged = allocator.allocate_tagged(layout, 3).unwrap();
+        unsafe {
+            allocator.deallocate(first.cast(), layout);
+            // Compacting moves the tagged slab into slot 0 without retagging it
+            assert_eq!(allocator.compact(|_, _| {}), 1);
+            assert_eq!(allocator.tag_of(first.cast()), Some(3));
+            assert_eq!(allocator.tag_of(tagged.cast()), None);
+            assert_eq!(allocator.bytes_used_by(3), 8);
+            assert_eq!(allocator.blocks[0].peak(), 2);
+
+            // As does growing it into the next section
+            let grown = allocator
+                .grow(first.cast(), layout, Layout::new::<[u8; 40]>())
+                .unwrap();
+            assert_eq!(allocator.tag_of(grown.cast()), Some(3));
+            assert_eq!(allocator.bytes_used_by(3), 64);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::api::{AllocError, Allocator};
+use crate::{dangling, SlabAllocator};
+use core::alloc;
+use core::cell::Cell;
+use core::mem;
+use core::ptr;
+
+/// Every chunk starts with a count of its live allocations
+type Header = usize;
+
+/// Allocator packing many tiny allocations into single slabs ("chunks") by bump allocation.
+/// Each chunk counts its live allocations and is returned to the [`SlabAllocator`] once they
+/// have all been freed. Allocations larger than `limit` bytes are passed straight through.
+///
+/// The pool is not thread safe; use one per thread or task
+pub struct TinyPool<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    chunk: alloc::Layout,
+    limit: usize,
+    current: Cell<Option<ptr::NonNull<u8>>>,
+    offset: Cell<usize>,
+    end: Cell<usize>,
+}
+
+impl<'a, 'm, const N: usize> TinyPool<'a, 'm, N> {
+    /// Creates a pool taking chunks of `chunk_size` bytes from `allocator` and packing
+    /// allocations of up to `limit` bytes into them. Panics if any section of the allocator is
+    /// [`spanning`](crate::Section::spanning), as a chunk must be a single slab
+    pub fn new(allocator: &'a SlabAllocator<'m, N>, chunk_size: usize, limit: usize) -> Self {
+        assert!(
+            allocator
+                .blocks
+                .iter()
+                .all(|section| section.runs.is_none()),
+            "Chunks cannot be taken from spanning sections"
+        );
+        Self {
+            allocator,
+            chunk: alloc::Layout::array::<u8>(chunk_size.max(mem::size_of::<Header>()))
+                .expect("Chunk size too large"),
+            limit,
+            current: Cell::new(None),
+            offset: Cell::new(0),
+            end: Cell::new(0),
+        }
+    }
+
+    /// The live allocation count of the chunk `ptr` was bump allocated from
+    fn header(&self, ptr: ptr::NonNull<u8>) -> ptr::NonNull<Header> {
+        let (_, section, slot) = self
+            .allocator
+            .locate(ptr)
+            .expect("Pointer was not allocated by this pool");
+        section.slot_ptr(slot).cast()
+    }
+
+    /// Replaces the current chunk with a fresh one
+    fn next_chunk(&self) -> Result<(), AllocError> {
+        if let Some(current) = self.current.take() {
+            // SAFETY: the chunk is live until its count reaches zero
+            if unsafe { current.cast::<Header>().read_unaligned() } == 0 {
+                unsafe { self.allocator.deallocate(current, self.chunk) };
+            }
+        }
+        let chunk = self.allocator.allocate(self.chunk)?;
+        // SAFETY: the chunk is at least as large as the header
+        unsafe { chunk.cast::<Header>().write_unaligned(0) };
+        self.current.set(Some(chunk.cast()));
+        self.offset.set(mem::size_of::<Header>());
+        self.end.set(chunk.len());
+        Ok(())
+    }
+}
+
+unsafe impl<'a, 'm, const N: usize> Allocator for TinyPool<'a, 'm, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations need no memory, so take no room in a chunk
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        if layout.size() > self.limit {
+            return self.allocator.allocate(layout);
+        }
+        for _ in 0..2 {
+            if let Some(chunk) = self.current.get() {
+                let base = chunk.as_ptr() as usize;
+                let start = (base + self.offset.get()).next_multiple_of(layout.align()) - base;
+                if start + layout.size() <= self.end.get() {
+                    self.offset.set(start + layout.size());
+                    // SAFETY: the allocation lies within the chunk
+                    unsafe {
+                        let count = chunk.cast::<Header>();
+                        count.write_unaligned(count.read_unaligned() + 1);
+                        return Ok(ptr::NonNull::slice_from_raw_parts(
+                            chunk.add(start),
+                            layout.size(),
+                        ));
+                    }
+                }
+            }
+            self.next_chunk()?;
+        }
+        Err(AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        if layout.size() > self.limit {
+            return self.allocator.deallocate(ptr, layout);
+        }
+        let count = self.header(ptr);
+        count.write_unaligned(count.read_unaligned() - 1);
+        if count.read_unaligned() == 0 {
+            if self.current.get() == Some(count.cast()) {
+                // Reuse the current chunk from the start
+                self.offset.set(mem::size_of::<Header>());
+            } else {
+                self.allocator.deallocate(count.cast(), self.chunk);
+            }
+        }
+    }
+}
+
+impl<'a, 'm, const N: usize> Drop for TinyPool<'a, 'm, N> {
+    fn drop(&mut self) {
+        if let Some(current) = self.current.get() {
+            // SAFETY: the chunk is live until its count reaches zero
+            if unsafe { current.cast::<Header>().read_unaligned() } == 0 {
+                unsafe { self.allocator.deallocate(current, self.chunk) };
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "nightly"))]
+mod test {
+    use crate::*;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn packing() {
+        extern crate std;
+        let mut buf = [0u8; 1 + 8 * 64 + 256];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(64, AtomicU8::new(0)),
+                Section::new(256, AtomicBool::new(false)),
+            ],
+            &mut buf[1..],
+        )
+        .unwrap();
+        {
+            let pool = TinyPool::new(&allocator, 64, 16);
+            let mut boxes = std::vec::Vec::new();
+            for i in 0..56u8 {
+                boxes.push(std::boxed::Box::new_in(i, &pool));
+            }
+            // 56 values and the header fill a single chunk
+            assert_eq!(allocator.blocks[0].free_slots(), 7);
+            let large = std::boxed::Box::new_in([0u8; 100], &pool);
+            assert_eq!(allocator.blocks[1].free_slots(), 0);
+
+            boxes.push(std::boxed::Box::new_in(56, &pool));
+            assert_eq!(allocator.blocks[0].free_slots(), 6);
+            for (i, b) in boxes.iter().enumerate() {
+                assert_eq!(**b as usize, i);
+            }
+
+            // The first chunk is returned once all its values are freed
+            boxes.truncate(56);
+            boxes.clear();
+            assert_eq!(allocator.blocks[0].free_slots(), 7);
+            drop(large);
+        }
+        assert_eq!(allocator.blocks[0].free_slots(), 8);
+        assert_eq!(allocator.blocks[1].free_slots(), 1);
+    }
+
+    #[test]
+    fn zero_sized() {
+        let mut buf = [0u8; 8 * 64];
+        let allocator =
+            SlabAllocator::new([Section::new(64, AtomicU8::new(0))], &mut buf[..]).unwrap();
+        let pool = TinyPool::new(&allocator, 64, 56);
+        let layout = core::alloc::Layout::new::<[u8; 56]>();
+        let value = pool.allocate(layout).unwrap();
+        // The chunk is full, so a zero sized allocation must not land at its end
+        let empty = pool.allocate(core::alloc::Layout::new::<()>()).unwrap();
+        unsafe {
+            pool.deallocate(empty.cast(), core::alloc::Layout::new::<()>());
+            pool.deallocate(value.cast(), layout);
+        }
+        assert_eq!(allocator.blocks[0].free_slots(), 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "Chunks cannot be taken from spanning sections")]
+    fn spanning() {
+        let mut buf = [0u8; 8 * 64];
+        let allocator = SlabAllocator::new(
+            [Section::new(64, AtomicU8::new(0)).spanning()],
+            &mut buf[..],
+        )
+        .unwrap();
+        TinyPool::new(&allocator, 64, 16);
+    }
+}
@@ -0,0 +1,99 @@
+//! Events emitted through `log` and `tracing` when their features are enabled.
+//! Every function is a no-op unless one of them is
+#[cfg(any(feature = "log", feature = "tracing"))]
+use crate::event::{Occupancy, OomDump};
+use crate::section::Section;
+use core::alloc;
+
+/// Emits an event at `$level` through each enabled logging facade
+macro_rules! emit {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::$level!(target: "slab_alloc", $($arg)+);
+        #[cfg(feature = "tracing")]
+        tracing::$level!(target: "slab_alloc", $($arg)+);
+    };
+}
+
+/// An allocator was created over `len` bytes with the sections `blocks`
+#[inline]
+pub(crate) fn created(_blocks: &[Section], _len: usize) {
+    emit!(debug, "created over {} bytes:{}", _len, Occupancy(_blocks));
+}
+
+/// The last free slot of section `index` of `blocks` was allocated
+#[cfg(any(feature = "log", feature = "tracing"))]
+#[inline]
+pub(crate) fn exhausted(_blocks: &[Section], _index: usize) {
+    emit!(
+        info,
+        "section {} of {} byte slabs exhausted",
+        _index,
+        _blocks[_index].size
+    );
+}
+
+/// An allocation of `layout` could not be satisfied from `blocks`
+#[inline]
+pub(crate) fn failed(_blocks: &[Section], _layout: alloc::Layout) {
+    emit!(
+        warn,
+        "{}",
+        OomDump {
+            layout: _layout,
+            sections: _blocks,
+        }
+    );
+}
+
+#[cfg(all(test, feature = "log", feature = "std"))]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::AtomicBool;
+    use std::string::{String, ToString};
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Logger;
+
+    impl log::Log for Logger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target() == "slab_alloc"
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                LINES.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn log() {
+        log::set_logger(&Logger).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let mut buf = Aligned([0u8; 8]);
+        let allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert!(allocator.allocate(layout).is_err());
+        unsafe { allocator.deallocate(slab.cast(), layout) };
+
+        assert_eq!(
+            *LINES.lock().unwrap(),
+            [
+                "created over 8 bytes: 8:0/1",
+                "section 0 of 8 byte slabs exhausted",
+                "OOM 8/8: 8:1/1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::SlabAllocator;
+use core::fmt;
+
+/// The first violation of the allocator's invariants found by [`SlabAllocator::verify`].
+/// Sections are numbered as in [`SlotInfo`](crate::SlotInfo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Corruption {
+    /// A section's slabs lie outside the allocator's buffer
+    OutOfBounds {
+        /// The index of the section
+        section: usize,
+    },
+    /// The slabs of two sections overlap
+    Overlap {
+        /// The index of the first section
+        first: usize,
+        /// The index of the second section
+        second: usize,
+    },
+    /// A section does not start on its alignment
+    Misaligned {
+        /// The index of the section
+        section: usize,
+    },
+    /// A section's gauge disagrees with its count of allocated slots
+    GaugeMismatch {
+        /// The index of the section
+        section: usize,
+        /// The value of the gauge
+        gauge: u32,
+        /// The number of slots allocated
+        allocated: u32,
+    },
+    /// A slot's memory does not map back to the slot
+    SlotMismatch {
+        /// The index of the section
+        section: usize,
+        /// The slot
+        slot: u32,
+    },
+    /// A free slab of a pre-zeroed section has been written to
+    DirtyFreeSlab {
+        /// The index of the section
+        section: usize,
+        /// The slot
+        slot: u32,
+    },
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { section } => write!(f, "section {section} outside the buffer"),
+            Self::Overlap { first, second } => {
+                write!(f, "sections {first} and {second} overlap")
+            }
+            Self::Misaligned { section } => write!(f, "section {section} misaligned"),
+            Self::GaugeMismatch {
+                section,
+                gauge,
+                allocated,
+            } => write!(
+                f,
+                "section {section} gauge {gauge} but {allocated} slots allocated"
+            ),
+            Self::SlotMismatch { section, slot } => {
+                write!(f, "slot {section}:{slot} does not map back to itself")
+            }
+            Self::DirtyFreeSlab { section, slot } => {
+                write!(
+                    f,
+                    "free slab {section}:{slot} of pre-zeroed section written"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for Corruption {}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Checks the allocator's internal invariants, returning the first violation found: that
+    /// sections lie within the buffer on their alignment without overlapping, that gauges match
+    /// the slots allocated, that slabs map back to their slots and that the free slabs of
+    /// pre-zeroed sections are still zero
+    pub fn verify(&mut self) -> Result<(), Corruption> {
+        let start = self.memory.cast::<u8>().as_ptr() as usize;
+        let end = start + self.memory.len();
+        let sections = || self.indexed();
+        let span = |section: &crate::Section| {
+            let region = section.region.as_ptr() as usize;
+            region..region + section.capacity()
+        };
+
+        for (position, (index, section)) in sections().enumerate() {
+            let range = span(section);
+            if index < N && (range.start < start || range.end > end) {
+                return Err(Corruption::OutOfBounds { section: index });
+            }
+            if !range.start.is_multiple_of(section.placement()) {
+                return Err(Corruption::Misaligned { section: index });
+            }
+            if let Some((first, _)) = sections().take(position).find(|(_, other)| {
+                let other = span(other);
+                !range.is_empty()
+                    && !other.is_empty()
+                    && other.start < range.end
+                    && range.start < other.end
+            }) {
+                return Err(Corruption::Overlap {
+                    first,
+                    second: index,
+                });
+            }
+
+            let allocated = section.total_slots() - section.free_slots();
+            if section.gauge().get() != allocated {
+                return Err(Corruption::GaugeMismatch {
+                    section: index,
+                    gauge: section.gauge().get(),
+                    allocated,
+                });
+            }
+
+            for slot in 0..section.total_slots() {
+                let slab = section.slot_ptr(slot);
+                if section.size > 0 && section.slot_of(slab.cast()) != Some(slot) {
+                    return Err(Corruption::SlotMismatch {
+                        section: index,
+                        slot,
+                    });
+                }
+                // SAFETY: the slab is free and the allocator exclusively borrowed, so nothing
+                // else accesses it
+                if section.zeroed
+                    && !section.is_allocated(slot)
+                    && unsafe { slab.as_ref() }.iter().any(|&byte| byte != 0)
+                {
+                    return Err(Corruption::DirtyFreeSlab {
+                        section: index,
+                        slot,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+    #[test]
+    fn verify() {
+        let mut buf = Aligned([0u8; 8 * 8 + 16]);
+        let mut allocator = SlabAllocator::new(
+            [
+                Section::new(8, AtomicU8::new(0)).pre_zeroed(),
+                Section::new(16, AtomicBool::new(false)),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        let layout = Layout::new::<u64>();
+        let slab = allocator.allocate(layout).unwrap();
+        assert_eq!(allocator.verify(), Ok(()));
+
+        // A bit flipped in a bitmap
+        let flip = |allocator: &SlabAllocator<2>, value| match &allocator.blocks[1].allocated {
+            Atomics::Bool(allocated) => allocated.store(value, Ordering::Relaxed),
+            _ => unreachable!(),
+        };
+        flip(&allocator, true);
+        assert_eq!(
+            allocator.verify(),
+            Err(Corruption::GaugeMismatch {
+                section: 1,
+                gauge: 0,
+                allocated: 1
+            })
+        );
+        flip(&allocator, false);
+
+        // A write through a stale pointer after the slab was freed
+        unsafe {
+            allocator.deallocate(slab.cast(), layout);
+            slab.cast::<u8>().write(1);
+        }
+        assert_eq!(
+            allocator.verify(),
+            Err(Corruption::DirtyFreeSlab {
+                section: 0,
+                slot: 0
+            })
+        );
+    }
+
+    #[test]
+    fn added_regions() {
+        const BANK: usize = core::mem::size_of::<Section>() + 64 + 8 * 8;
+        let mut buf = Aligned([0u8; 8]);
+        let (mut first, mut second) = (Aligned([0u8; BANK]), Aligned([0u8; BANK]));
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        allocator
+            .add_region(Section::new(8, AtomicU8::new(0)), &mut first.0[..])
+            .unwrap();
+        allocator
+            .add_region(Section::new(8, AtomicU8::new(0)), &mut second.0[..])
+            .unwrap();
+        // Added sections are only compared with the others, never with themselves
+        assert_eq!(allocator.verify(), Ok(()));
+    }
+}
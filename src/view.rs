@@ -0,0 +1,81 @@
+use crate::api::{AllocError, Allocator};
+use crate::{dangling, SlabAllocator};
+use core::alloc;
+use core::ptr;
+
+/// Allocator drawing only from some of the sections of a [`SlabAllocator`], so code given a view
+/// cannot consume size classes reserved for others. Created by [`SlabAllocator::view`]
+pub struct SlabView<'a, 'm, const N: usize> {
+    allocator: &'a SlabAllocator<'m, N>,
+    allowed: [bool; N],
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// A view of the allocator that only allocates from the sections with the given indices
+    pub fn view(&self, sections: &[usize]) -> SlabView<'_, 'm, N> {
+        let mut allowed = [false; N];
+        for &index in sections {
+            if let Some(allowed) = allowed.get_mut(index) {
+                *allowed = true;
+            }
+        }
+        SlabView {
+            allocator: self,
+            allowed,
+        }
+    }
+}
+
+unsafe impl<'a, 'm, const N: usize> Allocator for SlabView<'a, 'm, N> {
+    fn allocate(&self, layout: alloc::Layout) -> Result<ptr::NonNull<[u8]>, AllocError> {
+        // Zero sized allocations need no memory, so take no slab
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        let slab = self
+            .allocator
+            .find_slab(layout, |index| self.allowed.get(index) == Some(&true));
+        match slab {
+            Ok(slab) => self.allocator.served(layout, slab),
+            Err(_) => self.allocator.failed(layout),
+        }
+        slab
+    }
+
+    unsafe fn deallocate(&self, ptr: ptr::NonNull<u8>, layout: alloc::Layout) {
+        self.allocator.deallocate(ptr, layout)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn restricted() {
+        let mut buf = [0u8; 128];
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicBool::new(false)),
+                Section::new(8, AtomicU8::new(0)),
+                Section::new(32, AtomicBool::new(false)),
+            ],
+            &mut buf[..],
+        )
+        .unwrap();
+        let view = allocator.view(&[0, 2]);
+
+        let layout = Layout::new::<[u8; 4]>();
+        let small = view.allocate(layout).unwrap();
+        assert_eq!(small.len(), 4);
+        // The 8 byte section is skipped
+        assert_eq!(view.allocate(layout).unwrap().len(), 32);
+        assert!(view.allocate(layout).is_err());
+        assert!(allocator.allocate(layout).is_ok());
+
+        unsafe { view.deallocate(small.cast(), layout) };
+        assert!(view.allocate(layout).is_ok());
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(feature = "async")]
+use crate::future;
+use crate::{find_section, sanitize, SlabAllocator};
+use core::ptr;
+
+/// The granularity pages are touched at, no larger than the page size of any common MMU
+pub const PAGE_SIZE: usize = 4096;
+
+/// Reads and rewrites a byte so its page is mapped writable and its line is cached
+///
+/// # Safety
+/// `byte` must be valid for reads and writes and not accessed concurrently
+unsafe fn touch(byte: ptr::NonNull<u8>) {
+    byte.write_volatile(byte.read_volatile());
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Touches every page of the buffer so later allocations don't pay page fault costs, and the
+    /// first byte of every free slot as well if `touch_slots` is set.
+    /// Free slots are held while they are touched, without counting towards the slots in use or
+    /// their peak, and memory in live allocations is left alone
+    pub fn warm_up(&self, touch_slots: bool) {
+        for section in &self.blocks {
+            for slot in 0..section.total_slots() {
+                let warm = || {
+                    sanitize::unpoison(section.slot_ptr(slot));
+                    let slab = section.slot_ptr(slot).cast::<u8>();
+                    let first_page = slab.align_offset(PAGE_SIZE);
+                    // SAFETY: the offsets are within the held slot
+                    unsafe {
+                        if touch_slots && first_page != 0 {
+                            touch(slab);
+                        }
+                        for offset in (first_page..section.size).step_by(PAGE_SIZE) {
+                            touch(slab.add(offset));
+                        }
+                    }
+                    sanitize::poison(section.slot_ptr(slot));
+                };
+                if section.with_free(slot, warm) {
+                    // An allocation may have been refused while the slot was held
+                    #[cfg(feature = "async")]
+                    future::wake(section);
+                }
+            }
+        }
+
+        // Pages starting outside every slot, in the padding between sections, the gaps left by
+        // boundaries and coloring or the end of the buffer, are never handed out
+        let start = self.memory.cast::<u8>();
+        let first_page = start.align_offset(PAGE_SIZE).min(self.memory.len());
+        let pages = core::iter::once(0).chain((first_page..self.memory.len()).step_by(PAGE_SIZE));
+        for offset in pages.filter(|&offset| offset < self.memory.len()) {
+            // SAFETY: the offset is within the buffer
+            let byte = unsafe { start.add(offset) };
+            if find_section(&self.blocks, byte).is_some() {
+                continue;
+            }
+            let page = ptr::NonNull::slice_from_raw_parts(byte, 1);
+            sanitize::unpoison(page);
+            // SAFETY: no slab covers the byte
+            unsafe { touch(byte) };
+            sanitize::poison(page);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PAGE_SIZE;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8};
+
+    #[test]
+    fn warm_up() {
+        let mut buf = std::vec![0u8; 8 * PAGE_SIZE + 100];
+        let allocator = SlabAllocator::new(
+            [Section::new(PAGE_SIZE / 2, AtomicU8::new(0))],
+            &mut buf[..],
+        )
+        .unwrap();
+
+        let slab = allocator.allocate(Layout::new::<u8>()).unwrap();
+        unsafe { slab.cast::<u8>().write(7) };
+        allocator.warm_up(true);
+        allocator.warm_up(false);
+
+        assert_eq!(allocator.blocks[0].free_slots(), 7);
+        assert_eq!(unsafe { slab.cast::<u8>().read() }, 7);
+    }
+
+    #[test]
+    fn padded_sections() {
+        let mut buf = crate::test::Aligned([0u8; 32 + 32 + 64 + 16]);
+        let allocator = SlabAllocator::new(
+            [
+                Section::new(4, AtomicU8::new(0)),
+                Section::new(64, AtomicBool::new(false)).with_align(64),
+            ],
+            &mut buf.0[..],
+        )
+        .unwrap();
+        // The padding before the second section is touched without disturbing the slabs
+        let slab = allocator.allocate(Layout::new::<[u8; 64]>()).unwrap();
+        allocator.warm_up(true);
+        assert_eq!(allocator.blocks[1].free_slots(), 0);
+        // Holding free slots while touching them does not count them as used
+        assert_eq!(
+            allocator.blocks.each_ref().map(|section| section.peak()),
+            [0, 1]
+        );
+        unsafe { allocator.deallocate(slab.cast(), Layout::new::<[u8; 64]>()) };
+    }
+}
@@ -0,0 +1,100 @@
+use crate::atomic::Ordering;
+use crate::section::Section;
+use crate::{Event, SlabAllocator};
+use core::ptr;
+
+/// Clears the low flag of `section` once frees bring it back to its watermark
+pub(crate) fn check_recovered(section: &Section) {
+    if section.low.load(Ordering::Relaxed) && section.free_slots() >= section.watermark {
+        section.low.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<'m, const N: usize> SlabAllocator<'m, N> {
+    /// Which of the allocator's own sections have fewer slots free than their watermark
+    pub fn low_sections(&self) -> [bool; N] {
+        core::array::from_fn(|index| self.blocks[index].is_low())
+    }
+
+    /// Flags `section` as low and reports it to the observer if an allocation just took its free
+    /// slots below its watermark
+    pub(crate) fn check_low(&self, section: &Section) {
+        let free = section.free_slots();
+        if free >= section.watermark || section.low.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        if let Some(observer) = self.observer {
+            if let Some((index, _)) = self.indexed().find(|(_, other)| ptr::eq(*other, section)) {
+                observer(&Event::LowMemory(index, free));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::Aligned;
+    use crate::*;
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+    static LOW: AtomicUsize = AtomicUsize::new(0);
+
+    fn observer(event: &Event<'_>) {
+        if let Event::LowMemory(section, free) = event {
+            assert_eq!((*section, *free), (0, 1));
+            LOW.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn watermark() {
+        let mut buf = test::buffer();
+        let [small, large] = test::sections(0b1111_0000);
+        let mut allocator =
+            SlabAllocator::new([small.with_watermark(2), large], &mut buf.0[..]).unwrap();
+        allocator.set_observer(observer);
+        let layout = Layout::new::<u64>();
+
+        let slabs: [_; 3] = core::array::from_fn(|_| allocator.allocate(layout).unwrap());
+        assert_eq!(allocator.low_sections(), [true, false]);
+        assert_eq!(LOW.load(Ordering::Relaxed), 1);
+        unsafe { allocator.deallocate(slabs[1].cast(), layout) };
+        assert_eq!(allocator.low_sections(), [false, false]);
+
+        allocator.allocate(layout).unwrap();
+        assert_eq!(LOW.load(Ordering::Relaxed), 2);
+    }
+
+    static LOW_SECTION: AtomicUsize = AtomicUsize::new(0);
+
+    fn added_observer(event: &Event<'_>) {
+        if let Event::LowMemory(section, _) = event {
+            LOW_SECTION.store(*section, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn added_sections() {
+        const BANK: usize = core::mem::size_of::<Section>() + 64 + 8 * 8;
+        let mut buf = Aligned([0u8; 8]);
+        let (mut first, mut second) = (Aligned([0u8; BANK]), Aligned([0u8; BANK]));
+        let mut allocator =
+            SlabAllocator::new([Section::new(8, AtomicBool::new(false))], &mut buf.0[..]).unwrap();
+        allocator.set_observer(added_observer);
+        allocator
+            .add_region(
+                Section::new(8, AtomicBool::new(false)).with_watermark(1),
+                &mut first.0[..],
+            )
+            .unwrap();
+        allocator
+            .add_region(Section::new(4, AtomicU8::new(0)), &mut second.0[..])
+            .unwrap();
+        let layout = Layout::new::<u64>();
+        allocator.allocate(layout).unwrap();
+        // The first region added is reported by the index it was given, not its position
+        allocator.allocate(layout).unwrap();
+        assert_eq!(LOW_SECTION.load(Ordering::Relaxed), 1);
+    }
+}